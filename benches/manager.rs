@@ -0,0 +1,133 @@
+//! Throughput of the `Manager` hot path.
+//!
+//! Every operation is an index map lookup of the client's account followed by, at most,
+//! a hash map lookup of the transaction, so all operations are expected to be O(1) amortized.
+//! Throughput per element should therefore remain flat as the number of accounts grows.
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use std::hint::black_box;
+use transactor::manager::Manager;
+
+const ACCOUNTS: u16 = 10_000;
+const INITIAL_DEPOSIT: u64 = 1_000_000;
+
+/// Construct a `Manager` with an account for every client, each funded by the transaction
+/// matching its client ID.
+fn seeded_manager() -> Manager {
+    let mut manager = Manager::new();
+
+    for client in 0..ACCOUNTS {
+        manager
+            .deposit(client, client.into(), INITIAL_DEPOSIT, None)
+            .expect("Failed to deposit");
+    }
+
+    manager
+}
+
+/// Generate a deterministic pseudo-random sequence of client IDs using xorshift.
+fn random_clients(len: usize) -> Vec<u16> {
+    let mut state: u32 = 0x2545_f491;
+
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            (state % u32::from(ACCOUNTS)) as u16
+        })
+        .collect()
+}
+
+fn bench_manager(c: &mut Criterion) {
+    let mut group = c.benchmark_group("manager");
+    group.throughput(Throughput::Elements(ACCOUNTS.into()));
+
+    group.bench_function("sequential_deposits", |b| {
+        b.iter_batched(
+            seeded_manager,
+            |mut manager| {
+                for client in 0..ACCOUNTS {
+                    let tx = u32::from(ACCOUNTS) + u32::from(client);
+                    black_box(manager.deposit(client, tx, 100, None)).ok();
+                }
+                manager
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.bench_function("new_account_deposits", |b| {
+        b.iter_batched(
+            Manager::new,
+            |mut manager| {
+                for client in 0..ACCOUNTS {
+                    black_box(manager.deposit(client, client.into(), 100, None)).ok();
+                }
+                manager
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    // The same deposits as `new_account_deposits`, applied in a single call
+    group.bench_function("new_account_bulk_deposits", |b| {
+        b.iter_batched(
+            Manager::new,
+            |mut manager| {
+                let deposits = (0..ACCOUNTS).map(|client| (client, client.into(), 100));
+                black_box(manager.bulk_deposit(deposits));
+                manager
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    let clients = random_clients(ACCOUNTS.into());
+    group.bench_function("random_withdrawals", |b| {
+        b.iter_batched(
+            seeded_manager,
+            |mut manager| {
+                for (tx, &client) in (u32::from(ACCOUNTS)..).zip(&clients) {
+                    black_box(manager.withdraw(client, tx, 100)).ok();
+                }
+                manager
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.bench_function("deposit_dispute", |b| {
+        b.iter_batched(
+            seeded_manager,
+            |mut manager| {
+                for client in 0..ACCOUNTS {
+                    let tx = u32::from(ACCOUNTS) + u32::from(client);
+                    black_box(manager.deposit(client, tx, 100, None)).ok();
+                    black_box(manager.dispute(client, tx)).ok();
+                }
+                manager
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.bench_function("dispute_chargeback", |b| {
+        b.iter_batched(
+            seeded_manager,
+            |mut manager| {
+                for client in 0..ACCOUNTS {
+                    black_box(manager.dispute(client, client.into())).ok();
+                    black_box(manager.chargeback(client, client.into())).ok();
+                }
+                manager
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_manager);
+criterion_main!(benches);
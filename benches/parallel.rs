@@ -0,0 +1,53 @@
+//! Sequential and parallel deposits into a large number of accounts, as when loading initial balances.
+//!
+//! Client IDs are a u16, so the deposits cycle through every client ID, and each account
+//! receives one or two deposits.
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use std::hint::black_box;
+use transactor::manager::Manager;
+
+const DEPOSITS: u32 = 100_000;
+
+/// Generate one deposit per transaction ID, cycling through every client ID.
+fn deposits() -> Vec<(u16, u32, u64)> {
+    (0..DEPOSITS)
+        .map(|tx| ((tx % (u32::from(u16::MAX) + 1)) as u16, tx, 100))
+        .collect()
+}
+
+fn bench_deposits(c: &mut Criterion) {
+    let mut group = c.benchmark_group("deposit_all");
+    group.throughput(Throughput::Elements(DEPOSITS.into()));
+
+    group.bench_function("sequential", |b| {
+        b.iter_batched(
+            deposits,
+            |deposits| {
+                let mut manager = Manager::new();
+                for (client, tx, amt) in deposits {
+                    black_box(manager.deposit(client, tx, amt, None)).ok();
+                }
+                manager
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.bench_function("parallel", |b| {
+        b.iter_batched(
+            deposits,
+            |deposits| {
+                let mut manager = Manager::new();
+                black_box(manager.par_deposit_all(deposits));
+                manager
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_deposits);
+criterion_main!(benches);
@@ -0,0 +1,80 @@
+//! Insertion and iteration speed of the `SortedManager` compared to the `Manager`.
+//!
+//! The `Manager` looks up accounts in O(1) but must sort to iterate in order of client ID,
+//! while the `SortedManager` looks up accounts in O(log n) and iterates in order without a sort step.
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use std::hint::black_box;
+use transactor::{manager::Manager, sorted_manager::SortedManager};
+
+const ACCOUNTS: u16 = 10_000;
+
+/// Generate a deterministic pseudo-random permutation of every client ID using xorshift.
+fn shuffled_clients() -> Vec<u16> {
+    let mut clients: Vec<u16> = (0..ACCOUNTS).collect();
+    let mut state: u32 = 0x2545_f491;
+
+    for i in (1..clients.len()).rev() {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        clients.swap(i, state as usize % (i + 1));
+    }
+
+    clients
+}
+
+fn bench_sorted(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sorted");
+    group.throughput(Throughput::Elements(ACCOUNTS.into()));
+
+    let clients = shuffled_clients();
+    let manager = || {
+        let mut manager = Manager::new();
+        for &client in &clients {
+            manager.deposit(client, client.into(), 100, None).ok();
+        }
+        manager
+    };
+    let sorted_manager = || {
+        let mut manager = SortedManager::new();
+        for &client in &clients {
+            manager.deposit(client, client.into(), 100, None).ok();
+        }
+        manager
+    };
+
+    group.bench_function("manager_insert", |b| b.iter(|| black_box(manager())));
+    group.bench_function("sorted_manager_insert", |b| {
+        b.iter(|| black_box(sorted_manager()))
+    });
+
+    group.bench_function("manager_sorted_iteration", |b| {
+        b.iter_batched(
+            manager,
+            |manager| {
+                for (client, acct) in manager.into_sorted_vec_by(|(a, _), (b, _)| a.cmp(b)) {
+                    black_box((client, acct));
+                }
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.bench_function("sorted_manager_iteration", |b| {
+        b.iter_batched(
+            sorted_manager,
+            |manager| {
+                for (client, acct) in &manager {
+                    black_box((client, acct));
+                }
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_sorted);
+criterion_main!(benches);
@@ -0,0 +1,56 @@
+//! Fuzz sequences of operations against an `Account`, checking its invariants after every operation.
+//! Each 13 byte chunk of input is interpreted as an `(op: u8 % 5, tx: u32, amount: u64)` tuple.
+//! Any panic or invariant violation is a bug.
+//!
+//! Run from the repository root with a nightly toolchain and `cargo-fuzz` installed:
+//!
+//! ```text
+//! cargo +nightly fuzz run account_ops
+//! ```
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use transactor::account::Account;
+
+const CHUNK: usize = 13;
+
+/// Amounts are bounded so the balance of a single input cannot exceed `u64::MAX`,
+/// focusing the fuzzer on the state transitions rather than arithmetic overflow.
+const MAX_AMOUNT: u64 = 1 << 40;
+
+/// Decode a chunk into an `(op, tx, amount)` tuple.
+fn decode(chunk: &[u8]) -> (u8, u32, u64) {
+    let op = chunk[0] % 5;
+    let tx = u32::from_le_bytes(chunk[1..5].try_into().unwrap());
+    let amount = u64::from_le_bytes(chunk[5..13].try_into().unwrap()) % MAX_AMOUNT;
+    (op, tx, amount)
+}
+
+fuzz_target!(|data: &[u8]| {
+    let mut chunks = data.chunks_exact(CHUNK).map(decode);
+
+    let Some((_, tx, amount)) = chunks.next() else {
+        return;
+    };
+
+    let Ok(mut account) = Account::new(tx, amount) else {
+        return;
+    };
+    account.validate_invariants().unwrap();
+
+    for (op, tx, amount) in chunks {
+        let _ = match op {
+            0 => account.deposit(tx, amount, None),
+            1 => account.withdraw(tx, amount),
+            2 => account.dispute(tx),
+            3 => account.resolve(tx),
+            _ => account.chargeback(tx),
+        };
+
+        if let Err(violation) = account.validate_invariants() {
+            panic!("invariant violated after op {op} on tx {tx}: {violation}");
+        }
+        assert_eq!(account.available() + account.held(), account.total());
+    }
+});
@@ -0,0 +1,21 @@
+//! Fuzz the deserialization of transaction records, including the `amount` quantity parsing.
+//! Every input must produce either a record or a well formed error, never a panic.
+//!
+//! Run from the repository root with a nightly toolchain and `cargo-fuzz` installed:
+//!
+//! ```text
+//! cargo +nightly fuzz run quantity_from_str
+//! ```
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use transactor::parse::TransactionRecord;
+
+fuzz_target!(|data: &[u8]| {
+    let mut rdr = csv::Reader::from_reader(data);
+
+    for result in rdr.deserialize::<TransactionRecord>() {
+        let _ = result;
+    }
+});
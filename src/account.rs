@@ -1,33 +1,53 @@
-use std::collections::{hash_map::Entry, HashMap};
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    io::Write,
+};
 
-use crate::error::TransactorError;
+use serde::{Deserialize, Serialize};
 
-/// A deposit transaction tracking the amount and whether its disputed.
-#[derive(Debug)]
-struct Deposit {
-    amount: u64,
+use crate::{error::TransactorError, fixed_point::FixedPoint, operation::Operation};
+
+/// A deposit transaction tracking the amount and whether its disputed, resolved, or charged back.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Deposit {
+    amount: FixedPoint,
     disputed: bool,
+    #[serde(default)]
+    resolved: bool,
+    #[serde(default)]
+    chargebacked: bool,
+    #[serde(default)]
+    description: Option<String>,
 }
 
 impl Deposit {
     /// Construct a new `Deposit` transaction.
-    fn new(amount: u64) -> Self {
+    fn new(amount: FixedPoint, description: Option<String>) -> Self {
         Deposit {
             amount,
             disputed: false,
+            resolved: false,
+            chargebacked: false,
+            description,
         }
     }
 
     /// Return whether the deposit is disputed.
     #[inline]
-    fn is_disputed(&self) -> bool {
+    pub fn is_disputed(&self) -> bool {
         self.disputed
     }
 
     /// Get the amount of funds this deposit represents.
     #[inline]
-    fn amount(&self) -> u64 {
-        self.amount
+    pub fn amount(&self) -> u64 {
+        self.amount.raw()
+    }
+
+    /// Get the reference string the deposit was tagged with, such as a payment ID or invoice number.
+    #[inline]
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
     }
 
     /// Set the `Deposit` transaction to disputed.
@@ -40,49 +60,316 @@ impl Deposit {
     #[inline]
     fn resolve(&mut self) {
         self.disputed = false;
+        self.resolved = true;
+    }
+
+    /// Return whether a dispute of the deposit has been resolved.
+    #[inline]
+    pub fn is_resolved(&self) -> bool {
+        self.resolved
+    }
+
+    /// Return whether the deposit has been charged back.
+    #[inline]
+    pub fn is_chargebacked(&self) -> bool {
+        self.chargebacked
+    }
+
+    /// Set the `Deposit` transaction to charged back, ending the dispute.
+    /// Any earlier resolved dispute is superseded by the chargeback.
+    #[inline]
+    fn chargeback(&mut self) {
+        self.disputed = false;
+        self.resolved = false;
+        self.chargebacked = true;
     }
 }
 
-/// A client account that maintains the historical deposits and current funds.
-#[derive(Debug)]
+/// A withdrawal transaction tracking the amount withdrawn.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Withdrawal {
+    amount: FixedPoint,
+}
+
+impl Withdrawal {
+    /// Construct a new `Withdrawal` transaction.
+    fn new(amount: FixedPoint) -> Self {
+        Withdrawal { amount }
+    }
+
+    /// Get the amount withdrawn.
+    #[inline]
+    pub fn amount(&self) -> u64 {
+        self.amount.raw()
+    }
+}
+
+/// A row of the deposit history written by `Account::export_history`.
+#[derive(Serialize)]
+struct HistoryRecord {
+    tx: u32,
+    amount: FixedPoint,
+    disputed: bool,
+    chargebacked: bool,
+}
+
+/// A client account that maintains the historical deposits and withdrawals and current funds.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Account {
-    available: u64,
-    held: u64,
+    available: FixedPoint,
+    held: FixedPoint,
     frozen: bool,
     deposits: HashMap<u32, Deposit>,
+    #[serde(default)]
+    withdrawals: HashMap<u32, Withdrawal>,
+    #[serde(default)]
+    op_count: usize,
 }
 
 impl Account {
     /// Create a new `Account` with an initial deposit.
+    /// A zero amount is refused, rather than spending the transaction ID on an empty deposit.
     #[inline]
-    pub fn new(tx: u32, available: u64) -> Self {
+    pub fn new(tx: u32, available: u64) -> Result<Self, TransactorError> {
+        if available == 0 {
+            return Err(TransactorError::ZeroAmount);
+        }
+
+        let available = FixedPoint::from_raw(available);
         let mut deposits = HashMap::new();
-        deposits.insert(tx, Deposit::new(available));
+        deposits.insert(tx, Deposit::new(available, None));
 
-        Account {
+        Ok(Account {
             available,
-            held: 0,
+            held: FixedPoint::ZERO,
             frozen: false,
             deposits,
+            withdrawals: HashMap::new(),
+            op_count: 1,
+        })
+    }
+
+    /// Create a new `Account` with no funds and no deposit history.
+    #[inline]
+    pub fn new_empty() -> Self {
+        Account {
+            available: FixedPoint::ZERO,
+            held: FixedPoint::ZERO,
+            frozen: false,
+            deposits: HashMap::new(),
+            withdrawals: HashMap::new(),
+            op_count: 0,
         }
     }
 
     /// Get the available funds.
     #[inline]
     pub fn available(&self) -> u64 {
-        self.available
+        self.available.raw()
     }
 
     /// Get the held funds.
     #[inline]
     pub fn held(&self) -> u64 {
-        self.held
+        self.held.raw()
     }
 
-    /// Get the total funds.
+    /// Get the total funds, saturating rather than overflowing if the account state is inconsistent.
     #[inline]
     pub fn total(&self) -> u64 {
-        self.available + self.held
+        self.available.saturating_add(self.held).raw()
+    }
+
+    /// Format the available funds as a decimal to the ten thousandths place, such as `1.0050`.
+    #[inline]
+    pub fn available_as_decimal(&self) -> String {
+        self.available.to_string_decimal()
+    }
+
+    /// Format the held funds as a decimal to the ten thousandths place.
+    #[inline]
+    pub fn held_as_decimal(&self) -> String {
+        self.held.to_string_decimal()
+    }
+
+    /// Format the total funds as a decimal to the ten thousandths place.
+    #[inline]
+    pub fn total_as_decimal(&self) -> String {
+        self.available.saturating_add(self.held).to_string_decimal()
+    }
+
+    /// Get the total funds, equivalent to `total`.
+    #[inline]
+    pub fn effective_balance(&self) -> u64 {
+        self.total()
+    }
+
+    /// Get the sum of every tracked deposit, regardless of dispute state.
+    /// The sum saturates rather than overflowing.
+    #[inline]
+    pub fn net_deposited(&self) -> u64 {
+        self.deposits
+            .values()
+            .map(|deposit| deposit.amount)
+            .fold(FixedPoint::ZERO, FixedPoint::saturating_add)
+            .raw()
+    }
+
+    /// Get the sum of the currently disputed deposits.
+    /// The sum saturates rather than overflowing.
+    #[inline]
+    pub fn pending_dispute_value(&self) -> u64 {
+        self.deposits
+            .values()
+            .filter(|deposit| deposit.is_disputed())
+            .map(|deposit| deposit.amount)
+            .fold(FixedPoint::ZERO, FixedPoint::saturating_add)
+            .raw()
+    }
+
+    /// Get the sum of the currently disputed deposits, the value at risk of being charged back.
+    /// Returns `None` if the sum overflows.
+    #[inline]
+    pub fn deposit_value_at_risk(&self) -> Option<u64> {
+        self.deposits
+            .values()
+            .filter(|deposit| deposit.is_disputed())
+            .try_fold(FixedPoint::ZERO, |sum, deposit| {
+                sum.checked_add(deposit.amount)
+            })
+            .map(FixedPoint::raw)
+    }
+
+    /// Get the description of the deposit with the transaction ID, if it was tagged with one.
+    #[inline]
+    pub fn deposit_description(&self, tx: u32) -> Option<&str> {
+        self.deposits.get(&tx).and_then(Deposit::description)
+    }
+
+    /// Get the amount held for the deposit with the transaction ID, if it is currently disputed.
+    #[inline]
+    pub fn dispute_amount(&self, tx: u32) -> Option<u64> {
+        self.deposits
+            .get(&tx)
+            .filter(|deposit| deposit.is_disputed())
+            .map(Deposit::amount)
+    }
+
+    /// Return whether the deposit with the transaction ID is currently disputed.
+    #[inline]
+    pub fn is_tx_disputed(&self, tx: u32) -> bool {
+        self.dispute_amount(tx).is_some()
+    }
+
+    /// Get the amount of the largest single deposit tracked by the `Account`, or 0 if there are none.
+    #[inline]
+    pub fn max_deposit_amount(&self) -> u64 {
+        self.deposits
+            .values()
+            .map(Deposit::amount)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Get the amount of the smallest single deposit tracked by the `Account`, or `u64::MAX` if there are none.
+    #[inline]
+    pub fn min_deposit_amount(&self) -> u64 {
+        self.deposits
+            .values()
+            .map(Deposit::amount)
+            .min()
+            .unwrap_or(u64::MAX)
+    }
+
+    /// Get the number of successful deposits, withdrawals, disputes, resolves, and chargebacks
+    /// applied to the `Account`. Fees and refused operations are not counted.
+    #[inline]
+    pub fn age_in_transactions(&self) -> usize {
+        self.op_count
+    }
+
+    /// Get the number of deposits tracked by the `Account`.
+    #[inline]
+    pub fn deposit_count(&self) -> usize {
+        self.deposits.len()
+    }
+
+    /// Iterate over the deposits tracked by the `Account`, paired with their transaction IDs.
+    #[inline]
+    pub fn deposit_iter(&self) -> impl Iterator<Item = (u32, &Deposit)> + '_ {
+        self.deposits.iter().map(|(&tx, deposit)| (tx, deposit))
+    }
+
+    /// Get the transaction ID, amount, and whether it is disputed of every deposit,
+    /// in ascending order of transaction ID.
+    #[inline]
+    pub fn deposit_timeline(&self) -> Vec<(u32, u64, bool)> {
+        let mut timeline: Vec<(u32, u64, bool)> = self
+            .deposits
+            .iter()
+            .map(|(&tx, deposit)| (tx, deposit.amount(), deposit.is_disputed()))
+            .collect();
+
+        timeline.sort_unstable_by_key(|&(tx, _, _)| tx);
+        timeline
+    }
+
+    /// Get the number of withdrawals tracked by the `Account`.
+    #[inline]
+    pub fn withdrawal_count(&self) -> usize {
+        self.withdrawals.len()
+    }
+
+    /// Iterate over the withdrawals tracked by the `Account`, paired with their transaction IDs.
+    #[inline]
+    pub fn withdrawal_iter(&self) -> impl Iterator<Item = (u32, &Withdrawal)> + '_ {
+        self.withdrawals
+            .iter()
+            .map(|(&tx, withdrawal)| (tx, withdrawal))
+    }
+
+    /// Return whether the transaction ID is used by a deposit or withdrawal of the `Account`.
+    #[inline]
+    fn has_transaction(&self, tx: u32) -> bool {
+        self.deposits.contains_key(&tx) || self.withdrawals.contains_key(&tx)
+    }
+
+    /// Iterate over the transaction IDs of the currently disputed deposits, in no particular order.
+    #[inline]
+    pub fn held_deposit_ids(&self) -> impl Iterator<Item = u32> + '_ {
+        self.deposits
+            .iter()
+            .filter(|(_, deposit)| deposit.is_disputed())
+            .map(|(&tx, _)| tx)
+    }
+
+    /// Get the number of deposits that were charged back.
+    #[inline]
+    pub fn chargeback_count(&self) -> usize {
+        self.deposits
+            .values()
+            .filter(|deposit| deposit.is_chargebacked())
+            .count()
+    }
+
+    /// Get the number of deposits that were disputed and then resolved, without being charged back.
+    #[inline]
+    pub fn resolved_count(&self) -> usize {
+        self.deposits
+            .values()
+            .filter(|deposit| deposit.is_resolved() && !deposit.is_chargebacked())
+            .count()
+    }
+
+    /// Get the fraction of deposits whose dispute was settled, by resolution or chargeback.
+    /// Deposits removed with `remove_chargeback_deposit` are not counted.
+    #[inline]
+    pub fn dispute_rate(&self) -> f64 {
+        if self.deposits.is_empty() {
+            return 0.0;
+        }
+
+        (self.chargeback_count() + self.resolved_count()) as f64 / self.deposits.len() as f64
     }
 
     /// Return whether the account is frozen.
@@ -91,36 +378,191 @@ impl Account {
         self.frozen
     }
 
-    /// Deposit funds into the `Account`.
-    /// If the account is frozen or there is a duplicate transaction id, the action will not execute.
+    /// Freeze or unfreeze the account administratively, regardless of any chargebacks.
+    /// An unfrozen account accepts operations again, while its charged back deposits remain charged back.
+    #[inline]
+    pub fn set_frozen(&mut self, frozen: bool) {
+        self.frozen = frozen;
+    }
+
+    /// Check the internal consistency of the account, describing the first violated invariant.
+    /// The total funds must be representable, the held funds must equal the sum of disputed deposits,
+    /// and a charged back deposit must never be disputed or resolved again.
+    /// A charged back deposit does not require the account to be frozen, as it may be unfrozen administratively.
+    pub fn validate_invariants(&self) -> Result<(), String> {
+        if self.available.checked_add(self.held).is_none() {
+            return Err(format!(
+                "available funds of {} and held funds of {} overflow the total",
+                self.available.raw(),
+                self.held.raw()
+            ));
+        }
+
+        let disputed = self.pending_dispute_value();
+
+        if self.held != disputed {
+            return Err(format!(
+                "held funds of {} do not match disputed deposits of {disputed}",
+                self.held.raw()
+            ));
+        }
+
+        if let Some((tx, _)) = self.deposits.iter().find(|(_, deposit)| {
+            deposit.is_chargebacked() && (deposit.is_disputed() || deposit.is_resolved())
+        }) {
+            return Err(format!("charged back deposit {tx} is disputed or resolved"));
+        }
+
+        Ok(())
+    }
+
+    /// Return whether a deposit with the transaction ID would succeed.
+    #[inline]
+    pub fn can_deposit(&self, tx: u32) -> bool {
+        !self.frozen && !self.has_transaction(tx)
+    }
+
+    /// Return whether a withdrawal of the amount with the transaction ID would succeed.
+    #[inline]
+    pub fn can_withdraw(&self, tx: u32, amt: u64) -> bool {
+        !self.frozen && !self.has_transaction(tx) && self.available >= FixedPoint::from_raw(amt)
+    }
+
+    /// Return whether a dispute of the transaction would succeed.
+    #[inline]
+    pub fn can_dispute(&self, tx: u32) -> bool {
+        !self.frozen && self.is_disputable(tx)
+    }
+
+    /// Return whether the deposit with the transaction ID exists, is neither disputed nor charged back,
+    /// and does not exceed the available funds.
+    /// Unlike `can_dispute`, the frozen state is not checked; hot path callers are expected
+    /// to check `is_frozen` once, rather than for every transaction.
+    #[inline]
+    pub fn is_disputable(&self, tx: u32) -> bool {
+        self.deposits.get(&tx).is_some_and(|deposit| {
+            !deposit.is_disputed() && !deposit.is_chargebacked() && deposit.amount <= self.available
+        })
+    }
+
+    /// Return whether a resolve of the transaction would succeed.
     #[inline]
-    pub fn deposit(&mut self, tx: u32, amt: u64) -> Result<(), TransactorError> {
+    pub fn can_resolve(&self, tx: u32) -> bool {
+        !self.frozen && self.deposits.get(&tx).is_some_and(Deposit::is_disputed)
+    }
+
+    /// Return whether a chargeback of the transaction would succeed.
+    #[inline]
+    pub fn can_chargeback(&self, tx: u32) -> bool {
+        self.can_resolve(tx)
+    }
+
+    /// Return whether the account can be closed: it holds no funds, has no disputed deposits, and is not frozen.
+    #[inline]
+    pub fn can_be_closed(&self) -> bool {
+        !self.frozen
+            && self.available == FixedPoint::ZERO
+            && self.held == FixedPoint::ZERO
+            && self.held_deposit_ids().next().is_none()
+    }
+
+    /// Deposit funds into the `Account`, optionally tagged with a description.
+    /// If the account is frozen, there is a duplicate transaction id,
+    /// or the total funds would overflow, the action will not execute.
+    #[inline]
+    pub fn deposit(
+        &mut self,
+        tx: u32,
+        amt: u64,
+        description: Option<String>,
+    ) -> Result<(), TransactorError> {
+        let amt = FixedPoint::from_raw(amt);
+
         if self.frozen {
             return Err(TransactorError::FrozenAccount);
         }
 
-        if let Entry::Vacant(entry) = self.deposits.entry(tx) {
-            entry.insert(Deposit::new(amt));
-            self.available += amt;
-            return Ok(());
+        if self.withdrawals.contains_key(&tx) {
+            return Err(TransactorError::DuplicateTxn(tx));
         }
 
-        Err(TransactorError::DuplicateTxn(tx))
+        let Entry::Vacant(entry) = self.deposits.entry(tx) else {
+            return Err(TransactorError::DuplicateTxn(tx));
+        };
+
+        // The total must stay representable, so later disputes and resolves cannot overflow either.
+        let available = self
+            .available
+            .checked_add(amt)
+            .filter(|available| available.checked_add(self.held).is_some())
+            .ok_or(TransactorError::BalanceOverflow { tx })?;
+
+        entry.insert(Deposit::new(amt, description));
+        self.available = available;
+        self.op_count += 1;
+        Ok(())
     }
 
-    /// Withdraw funds from the `Account`.
-    /// If the account is frozen or there is a lack of funds, the action will not execute.
+    /// Withdraw funds from the `Account`, tracking the withdrawal by its transaction ID.
+    /// If the account is frozen, the transaction ID is already used, or there is a lack of funds,
+    /// the action will not execute.
     #[inline]
-    pub fn withdraw(&mut self, amt: u64) -> Result<(), TransactorError> {
+    pub fn withdraw(&mut self, tx: u32, amt: u64) -> Result<(), TransactorError> {
         if self.frozen {
             return Err(TransactorError::FrozenAccount);
         }
 
-        if self.available < amt {
-            return Err(TransactorError::withdrawal_exceeds(self.available, amt));
+        if self.has_transaction(tx) {
+            return Err(TransactorError::DuplicateTxn(tx));
         }
 
-        self.available -= amt;
+        let amt = FixedPoint::from_raw(amt);
+        let available = self
+            .available
+            .checked_sub(amt)
+            .ok_or_else(|| TransactorError::withdrawal_exceeds(self.available.raw(), amt.raw()))?;
+
+        self.available = available;
+        self.withdrawals.insert(tx, Withdrawal::new(amt));
+        self.op_count += 1;
+        Ok(())
+    }
+
+    /// Deduct a platform fee from the `Account`'s available funds.
+    /// A frozen account is only charged when `apply_to_frozen` is set.
+    /// If there is a lack of funds, the action will not execute.
+    #[inline]
+    pub fn apply_fee(&mut self, amt: u64, apply_to_frozen: bool) -> Result<(), TransactorError> {
+        let amt = FixedPoint::from_raw(amt);
+
+        if self.frozen && !apply_to_frozen {
+            return Err(TransactorError::FrozenAccount);
+        }
+
+        self.available =
+            self.available
+                .checked_sub(amt)
+                .ok_or(TransactorError::InsufficientFundsForFee {
+                    available: self.available.raw(),
+                    attempted: amt.raw(),
+                })?;
+        Ok(())
+    }
+
+    /// Return previously withdrawn funds to the `Account`, reverting the withdrawal.
+    /// If the available funds would overflow, the withdrawal is kept.
+    #[inline]
+    pub(crate) fn restore(&mut self, tx: u32) -> Result<(), TransactorError> {
+        let Some(withdrawal) = self.withdrawals.get(&tx) else {
+            return Ok(());
+        };
+
+        self.available = self
+            .available
+            .checked_add(withdrawal.amount)
+            .ok_or(TransactorError::BalanceOverflow { tx })?;
+        self.withdrawals.remove(&tx);
+        self.op_count -= 1;
         Ok(())
     }
 
@@ -136,20 +578,30 @@ impl Account {
             .deposits
             .get_mut(&tx)
             .ok_or(TransactorError::NoTransaction(tx))?;
-        let amt = deposit.amount();
+        let amt = deposit.amount;
 
         if deposit.is_disputed() {
             return Err(TransactorError::AlreadyDisputedTxn(tx));
         }
 
-        if self.available < amt {
-            return Err(TransactorError::dispute_exceeds(self.available, amt));
+        if deposit.is_chargebacked() {
+            return Err(TransactorError::ChargebackedTxn(tx));
         }
 
+        let available = self
+            .available
+            .checked_sub(amt)
+            .ok_or_else(|| TransactorError::dispute_exceeds(self.available.raw(), amt.raw()))?;
+        let held = self
+            .held
+            .checked_add(amt)
+            .ok_or(TransactorError::BalanceOverflow { tx })?;
+
         deposit.dispute();
 
-        self.available -= amt;
-        self.held += amt;
+        self.available = available;
+        self.held = held;
+        self.op_count += 1;
         Ok(())
     }
 
@@ -171,11 +623,20 @@ impl Account {
             return Err(TransactorError::NonDisputedTxn(tx));
         }
 
-        let amt = deposit.amount();
+        let amt = deposit.amount;
+        let held = self
+            .held
+            .checked_sub(amt)
+            .ok_or(TransactorError::HeldUnderflow { tx })?;
+        let available = self
+            .available
+            .checked_add(amt)
+            .ok_or(TransactorError::BalanceOverflow { tx })?;
         deposit.resolve();
 
-        self.held -= amt;
-        self.available += amt;
+        self.held = held;
+        self.available = available;
+        self.op_count += 1;
         Ok(())
     }
 
@@ -197,18 +658,103 @@ impl Account {
             return Err(TransactorError::NonDisputedTxn(tx));
         }
 
-        let amt = deposit.amount();
-        deposit.resolve();
+        let amt = deposit.amount;
+        let held = self
+            .held
+            .checked_sub(amt)
+            .ok_or(TransactorError::HeldUnderflow { tx })?;
+        deposit.chargeback();
 
-        self.held -= amt;
+        self.held = held;
         self.frozen = true;
+        self.op_count += 1;
+        Ok(())
+    }
+
+    /// Remove a charged back deposit, freeing the memory of a transaction that can no longer be disputed.
+    /// If the transaction has not been charged back, the action will not execute.
+    #[inline]
+    pub fn remove_chargeback_deposit(&mut self, tx: u32) -> Result<(), TransactorError> {
+        let deposit = self
+            .deposits
+            .get(&tx)
+            .ok_or(TransactorError::NoTransaction(tx))?;
+
+        if !deposit.is_chargebacked() {
+            return Err(TransactorError::DepositNotChargeable(tx));
+        }
+
+        self.deposits.remove(&tx);
         Ok(())
     }
+
+    /// Serialize the deposit history as CSV and write it to the writer, in ascending order of transaction ID.
+    /// Each row holds the transaction ID, the amount, and whether the deposit is disputed or charged back.
+    #[inline]
+    pub fn export_history<W: Write>(&self, writer: W) -> Result<(), TransactorError> {
+        let mut history: Vec<(&u32, &Deposit)> = self.deposits.iter().collect();
+        history.sort_unstable_by_key(|&(&tx, _)| tx);
+
+        let mut wtr = csv::WriterBuilder::new()
+            .has_headers(false)
+            .from_writer(writer);
+        wtr.write_record(["tx", "amount", "disputed", "chargebacked"])?;
+
+        for (&tx, deposit) in history {
+            wtr.serialize(HistoryRecord {
+                tx,
+                amount: deposit.amount,
+                disputed: deposit.disputed,
+                chargebacked: deposit.chargebacked,
+            })?;
+        }
+
+        wtr.flush()?;
+        Ok(())
+    }
+
+    /// Apply an operation to a copy of the `Account`, returning the updated copy and leaving `self` unchanged.
+    /// Deposits, withdrawals, and fees require an amount. Fees are not charged to frozen accounts.
+    /// Transfers involve a second account and are refused, while registration leaves the copy unchanged.
+    /// Administrative unfreezes unfreeze the copy.
+    #[inline]
+    pub fn apply(
+        &self,
+        op: Operation,
+        tx: u32,
+        amt: Option<u64>,
+    ) -> Result<Account, TransactorError> {
+        let mut acct = self.clone();
+
+        match op {
+            Operation::Deposit => {
+                acct.deposit(tx, amt.ok_or(TransactorError::MissingAmount)?, None)
+            }
+            Operation::Withdrawal => acct.withdraw(tx, amt.ok_or(TransactorError::MissingAmount)?),
+            Operation::Dispute => acct.dispute(tx),
+            Operation::Resolve => acct.resolve(tx),
+            Operation::Chargeback => acct.chargeback(tx),
+            Operation::Transfer => Err(TransactorError::MissingTarget),
+            Operation::Fee => acct.apply_fee(amt.ok_or(TransactorError::MissingAmount)?, false),
+            Operation::Register => Ok(()),
+            Operation::AdminUnfreeze => {
+                acct.set_frozen(false);
+                Ok(())
+            }
+            Operation::AdminFreeze => {
+                acct.set_frozen(true);
+                Ok(())
+            }
+        }?;
+
+        Ok(acct)
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::error::TransactorError;
+    use crate::{error::TransactorError, fixed_point::FixedPoint, operation::Operation};
+    use proptest::prelude::*;
 
     use super::Account;
 
@@ -225,54 +771,487 @@ mod tests {
 
     #[test]
     fn deposit() {
-        let mut acct = Account::new(1, 0);
-        let result = acct.deposit(1, 100);
+        assert!(matches!(
+            Account::new(1, 0),
+            Err(TransactorError::ZeroAmount)
+        ));
+
+        let mut acct = Account::new(1, 100).unwrap();
+        let result = acct.deposit(1, 100, None);
+        acct.validate_invariants().unwrap();
 
         assert!(matches!(result, Err(TransactorError::DuplicateTxn(1))));
 
+        check_account(&acct, 100, 0, false);
+
+        acct.deposit(2, 100, None).expect("Failed to deposit");
+        acct.validate_invariants().unwrap();
+
+        check_account(&acct, 200, 0, false);
+    }
+
+    #[test]
+    fn deposit_description() {
+        let mut acct = Account::new(1, 100).unwrap();
+        acct.deposit(2, 50, Some("PAY-7781".to_string()))
+            .expect("Failed to deposit");
+
+        assert_eq!(acct.deposit_description(2), Some("PAY-7781"));
+        assert_eq!(acct.deposit_description(1), None);
+        assert_eq!(acct.deposit_description(3), None);
+
+        // A refused deposit leaves the original description in place
+        acct.deposit(2, 50, Some("PAY-7782".to_string()))
+            .unwrap_err();
+        assert_eq!(acct.deposit_description(2), Some("PAY-7781"));
+    }
+
+    #[test]
+    fn age_in_transactions() {
+        let mut acct = Account::new(1, 100).unwrap();
+        assert_eq!(acct.age_in_transactions(), 1);
+
+        acct.deposit(2, 50, None).expect("Failed to deposit");
+        acct.withdraw(3, 20).expect("Failed to withdraw");
+        acct.dispute(1).expect("Failed to dispute");
+        acct.resolve(1).expect("Failed to resolve");
+        acct.dispute(2).expect("Failed to dispute");
+        assert_eq!(acct.age_in_transactions(), 6);
+
+        // Refused operations and fees are not counted
+        acct.deposit(2, 50, None).unwrap_err();
+        acct.withdraw(4, 1000).unwrap_err();
+        acct.dispute(9).unwrap_err();
+        acct.resolve(1).unwrap_err();
+        acct.apply_fee(10, false).expect("Failed to charge fee");
+        assert_eq!(acct.age_in_transactions(), 6);
+
+        acct.chargeback(2).expect("Failed to chargeback");
+        acct.deposit(5, 10, None).unwrap_err();
+        assert_eq!(acct.age_in_transactions(), 7);
+        assert_eq!(acct.deposit_count(), 2);
+        assert_eq!(Account::new_empty().age_in_transactions(), 0);
+    }
+
+    #[test]
+    fn dispute_amount() {
+        let mut acct = Account::new(1, 100).unwrap();
+        acct.deposit(2, 50, None).expect("Failed to deposit");
+        acct.dispute(2).expect("Failed to dispute");
+
+        assert_eq!(acct.dispute_amount(3), None);
+        assert!(!acct.is_tx_disputed(3));
+        assert_eq!(acct.dispute_amount(1), None);
+        assert!(!acct.is_tx_disputed(1));
+        assert_eq!(acct.dispute_amount(2), Some(50));
+        assert!(acct.is_tx_disputed(2));
+
+        acct.resolve(2).expect("Failed to resolve");
+        assert_eq!(acct.dispute_amount(2), None);
+        assert!(!acct.is_tx_disputed(2));
+    }
+
+    #[test]
+    fn is_disputable() {
+        let mut acct = Account::new(1, 100).unwrap();
+        acct.deposit(2, 50, None).expect("Failed to deposit");
+        acct.deposit(3, 10, None).expect("Failed to deposit");
+        assert!(acct.is_disputable(1));
+        assert!(!acct.is_disputable(4));
+
+        acct.dispute(1).expect("Failed to dispute");
+        assert!(!acct.is_disputable(1));
+        assert!(acct.is_disputable(2));
+
+        // Deposits exceeding the available funds are not disputable
+        acct.withdraw(5, 20).expect("Failed to withdraw");
+        assert!(!acct.is_disputable(2));
+        assert!(acct.is_disputable(3));
+
+        // The frozen state is left to the caller
+        acct.chargeback(1).expect("Failed to chargeback");
+        assert!(acct.is_frozen());
+        assert!(acct.is_disputable(3));
+        assert!(!acct.can_dispute(3));
+    }
+
+    #[test]
+    fn set_frozen() {
+        let mut acct = Account::new(1, 100).unwrap();
+        acct.dispute(1).expect("Failed to dispute");
+        acct.chargeback(1).expect("Failed to chargeback");
+        assert!(acct.is_frozen());
+
+        acct.set_frozen(false);
+        acct.validate_invariants().unwrap();
+        acct.deposit(2, 50, None).expect("Failed to deposit");
+        check_account(&acct, 50, 0, false);
+        assert!(acct.deposits[&1].is_chargebacked());
+
+        acct.set_frozen(true);
+        acct.validate_invariants().unwrap();
+        assert!(matches!(
+            acct.deposit(3, 50, None),
+            Err(TransactorError::FrozenAccount)
+        ));
+    }
+
+    #[test]
+    fn min_max_deposit_amount() {
+        let mut acct = Account::new_empty();
+        assert_eq!(acct.max_deposit_amount(), 0);
+        assert_eq!(acct.min_deposit_amount(), u64::MAX);
+
+        for (tx, amt) in [(1, 300), (2, 50), (3, 1200), (4, 75)] {
+            acct.deposit(tx, amt, None).expect("Failed to deposit");
+        }
+        assert_eq!(acct.max_deposit_amount(), 1200);
+        assert_eq!(acct.min_deposit_amount(), 50);
+
+        // Disputed and charged back deposits are still tracked
+        acct.dispute(3).expect("Failed to dispute");
+        acct.chargeback(3).expect("Failed to chargeback");
+        assert_eq!(acct.max_deposit_amount(), 1200);
+
+        acct.remove_chargeback_deposit(3)
+            .expect("Failed to remove deposit");
+        assert_eq!(acct.max_deposit_amount(), 300);
+    }
+
+    #[test]
+    fn new_empty() {
+        let mut acct = Account::new_empty();
+        acct.validate_invariants().unwrap();
+
         check_account(&acct, 0, 0, false);
+        assert_eq!(acct.total(), 0);
+        assert!(acct.deposits.is_empty());
+        assert!(matches!(
+            acct.withdraw(1, 1),
+            Err(TransactorError::WithdrawalExceedsAvailable { .. })
+        ));
+        assert!(matches!(
+            acct.dispute(1),
+            Err(TransactorError::NoTransaction(1))
+        ));
 
-        acct.deposit(2, 100).expect("Failed to deposit");
+        acct.deposit(1, 100, None).expect("Failed to deposit");
+        acct.validate_invariants().unwrap();
 
         check_account(&acct, 100, 0, false);
+        check_deposit(&acct, 1, false);
+    }
+
+    #[test]
+    fn deposit_iter_settlement() {
+        let mut acct = Account::new(1, 100).unwrap();
+        acct.deposit(2, 50, None).expect("Failed to deposit");
+        acct.dispute(1).unwrap();
+        acct.resolve(1).unwrap();
+        acct.dispute(2).unwrap();
+        acct.chargeback(2).unwrap();
+
+        let mut deposits: Vec<_> = acct.deposit_iter().collect();
+        deposits.sort_by_key(|(tx, _)| *tx);
+
+        let (tx, resolved) = deposits[0];
+        assert_eq!(tx, 1);
+        assert_eq!(resolved.amount(), 100);
+        assert!(!resolved.is_disputed());
+        assert!(!resolved.is_chargebacked());
+        assert!(resolved.is_resolved());
+
+        let (tx, chargebacked) = deposits[1];
+        assert_eq!(tx, 2);
+        assert_eq!(chargebacked.amount(), 50);
+        assert!(!chargebacked.is_disputed());
+        assert!(chargebacked.is_chargebacked());
+        assert!(!chargebacked.is_resolved());
+    }
+
+    #[test]
+    fn financial_summary() {
+        let mut acct = Account::new(1, 100).unwrap();
+        acct.deposit(2, 50, None).expect("Failed to deposit");
+        acct.deposit(3, 25, None).expect("Failed to deposit");
+        acct.withdraw(4, 75).expect("Failed to withdraw");
+
+        assert_eq!(acct.net_deposited(), 175);
+        assert_eq!(acct.pending_dispute_value(), 0);
+        assert_eq!(acct.effective_balance(), 100);
+
+        acct.dispute(2).unwrap();
+        assert_eq!(acct.pending_dispute_value(), 50);
+        acct.dispute(3).unwrap();
+        assert_eq!(acct.pending_dispute_value(), 75);
+        assert_eq!(acct.effective_balance(), 100);
+
+        acct.resolve(2).unwrap();
+        assert_eq!(acct.pending_dispute_value(), 25);
+        acct.resolve(3).unwrap();
+        assert_eq!(acct.pending_dispute_value(), 0);
+        assert_eq!(acct.net_deposited(), 175);
+    }
+
+    #[test]
+    fn deposit_value_at_risk() {
+        let mut acct = Account::new(1, 100).unwrap();
+        acct.deposit(2, 50, None).expect("Failed to deposit");
+        assert_eq!(acct.deposit_value_at_risk(), Some(0));
+
+        acct.dispute(1).unwrap();
+        acct.dispute(2).unwrap();
+        assert_eq!(acct.deposit_value_at_risk(), Some(150));
+
+        acct.resolve(1).unwrap();
+        assert_eq!(acct.deposit_value_at_risk(), Some(50));
+    }
+
+    #[test]
+    fn net_deposited_saturates() {
+        let mut acct = Account::new(1, u64::MAX).unwrap();
+        acct.withdraw(3, u64::MAX).expect("Failed to withdraw");
+        acct.deposit(2, 1, None).expect("Failed to deposit");
+
+        assert_eq!(acct.net_deposited(), u64::MAX);
+    }
+
+    #[test]
+    fn held_deposit_ids() {
+        let mut acct = Account::new(1, 100).unwrap();
+        acct.deposit(2, 100, None).expect("Failed to deposit");
+        acct.deposit(3, 100, None).expect("Failed to deposit");
+        assert_eq!(acct.held_deposit_ids().count(), 0);
+
+        acct.dispute(1).unwrap();
+        acct.dispute(3).unwrap();
+        acct.resolve(1).unwrap();
+
+        assert_eq!(acct.held_deposit_ids().collect::<Vec<_>>(), [3]);
+    }
+
+    #[test]
+    fn settlement_counts() {
+        let mut acct = Account::new_empty();
+        assert_eq!(acct.dispute_rate(), 0.0);
+
+        for tx in 1..=4 {
+            acct.deposit(tx, 10, None).expect("Failed to deposit");
+        }
+
+        acct.dispute(1).unwrap();
+        acct.resolve(1).unwrap();
+        acct.dispute(2).unwrap();
+        acct.resolve(2).unwrap();
+        acct.dispute(3).unwrap();
+
+        assert_eq!(acct.chargeback_count(), 0);
+        assert_eq!(acct.resolved_count(), 2);
+        assert_eq!(acct.dispute_rate(), 0.5);
+
+        // A resolved deposit disputed again and charged back only counts as a chargeback
+        acct.dispute(2).unwrap();
+        acct.chargeback(2).unwrap();
+
+        assert_eq!(acct.chargeback_count(), 1);
+        assert_eq!(acct.resolved_count(), 1);
+        assert_eq!(acct.dispute_rate(), 0.5);
+    }
+
+    #[test]
+    fn remove_chargeback_deposit() {
+        let mut acct = Account::new(1, 100).unwrap();
+        acct.deposit(2, 50, None).expect("Failed to deposit");
+
+        assert!(matches!(
+            acct.remove_chargeback_deposit(1),
+            Err(TransactorError::DepositNotChargeable(1))
+        ));
+        assert!(matches!(
+            acct.remove_chargeback_deposit(3),
+            Err(TransactorError::NoTransaction(3))
+        ));
+
+        acct.dispute(1).unwrap();
+        assert!(matches!(
+            acct.remove_chargeback_deposit(1),
+            Err(TransactorError::DepositNotChargeable(1))
+        ));
+
+        acct.chargeback(1).unwrap();
+        assert!(acct.deposits[&1].is_chargebacked());
+        acct.remove_chargeback_deposit(1)
+            .expect("Failed to remove deposit");
+        acct.validate_invariants().unwrap();
+
+        assert_eq!(acct.deposit_count(), 1);
+        check_account(&acct, 50, 0, true);
     }
 
     #[test]
     fn withdraw() {
-        let mut acct = Account::new(1, 100);
-        acct.withdraw(99).expect("Failed to withdraw");
+        let mut acct = Account::new(1, 100).unwrap();
+        acct.withdraw(2, 99).expect("Failed to withdraw");
+        acct.validate_invariants().unwrap();
+
+        check_account(&acct, 1, 0, false);
+        assert_eq!(acct.withdrawal_count(), 1);
+        let (tx, withdrawal) = acct.withdrawal_iter().next().expect("No Withdrawal");
+        assert_eq!((tx, withdrawal.amount()), (2, 99));
+    }
+
+    #[test]
+    fn withdraw_duplicate_txn() {
+        let mut acct = Account::new(1, 100).unwrap();
+        acct.withdraw(2, 10).expect("Failed to withdraw");
+
+        // Withdrawals share the transaction ID space of the account with deposits
+        for tx in [1, 2] {
+            assert!(!acct.can_withdraw(tx, 10));
+            assert!(matches!(
+                acct.withdraw(tx, 10),
+                Err(TransactorError::DuplicateTxn(id)) if id == tx
+            ));
+        }
+        assert!(matches!(
+            acct.deposit(2, 10, None),
+            Err(TransactorError::DuplicateTxn(2))
+        ));
+        assert!(!acct.can_deposit(2));
 
-        check_account(&acct, 1, 0, false)
+        acct.validate_invariants().unwrap();
+        check_account(&acct, 90, 0, false);
+        assert_eq!(acct.withdrawal_count(), 1);
+        assert_eq!(acct.deposit_count(), 1);
     }
 
     #[test]
     fn dispute_resolve() {
-        let mut acct = Account::new(1, 100);
+        let mut acct = Account::new(1, 100).unwrap();
         acct.dispute(1).unwrap();
+        acct.validate_invariants().unwrap();
 
         check_account(&acct, 0, 100, false);
         check_deposit(&acct, 1, true);
 
         acct.resolve(1).unwrap();
+        acct.validate_invariants().unwrap();
 
         check_account(&acct, 100, 0, false);
     }
 
     #[test]
     fn chargeback() {
-        let mut acct = Account::new(1, 100);
+        let mut acct = Account::new(1, 100).unwrap();
         acct.dispute(1).unwrap();
+        acct.validate_invariants().unwrap();
         acct.chargeback(1).unwrap();
+        acct.validate_invariants().unwrap();
 
         check_account(&acct, 0, 0, true);
         check_deposit(&acct, 1, false);
     }
 
+    #[test]
+    fn apply() {
+        let acct = Account::new(1, 100).unwrap();
+
+        let deposited = acct
+            .apply(Operation::Deposit, 2, Some(50))
+            .expect("Failed to deposit");
+        check_account(&deposited, 150, 0, false);
+
+        let withdrawn = deposited
+            .apply(Operation::Withdrawal, 3, Some(25))
+            .expect("Failed to withdraw");
+        check_account(&withdrawn, 125, 0, false);
+
+        let disputed = withdrawn
+            .apply(Operation::Dispute, 1, None)
+            .expect("Failed to dispute");
+        check_account(&disputed, 25, 100, false);
+        check_deposit(&disputed, 1, true);
+
+        let resolved = disputed
+            .apply(Operation::Resolve, 1, None)
+            .expect("Failed to resolve");
+        check_account(&resolved, 125, 0, false);
+
+        let chargebacked = disputed
+            .apply(Operation::Chargeback, 1, None)
+            .expect("Failed to chargeback");
+        check_account(&chargebacked, 25, 0, true);
+
+        // Every intermediate account is left unchanged
+        check_account(&acct, 100, 0, false);
+        check_account(&deposited, 150, 0, false);
+        check_account(&withdrawn, 125, 0, false);
+        check_account(&disputed, 25, 100, false);
+        assert_eq!(acct.deposit_count(), 1);
+    }
+
+    #[test]
+    fn apply_refused() {
+        let acct = Account::new(1, 100).unwrap();
+
+        let result = acct.apply(Operation::Withdrawal, 2, Some(150));
+        assert!(matches!(
+            result,
+            Err(TransactorError::WithdrawalExceedsAvailable { .. })
+        ));
+
+        let result = acct.apply(Operation::Deposit, 2, None);
+        assert!(matches!(result, Err(TransactorError::MissingAmount)));
+
+        check_account(&acct, 100, 0, false);
+    }
+
+    #[test]
+    fn serde_round_trip() {
+        let mut acct = Account::new(1, 100).unwrap();
+        acct.deposit(2, 50, None).expect("Failed to deposit");
+        acct.deposit(3, 25, Some("INV-1042".to_string()))
+            .expect("Failed to deposit");
+        acct.dispute(1).expect("Failed to dispute");
+        acct.dispute(2).expect("Failed to dispute");
+        acct.resolve(2).expect("Failed to resolve");
+
+        let json = serde_json::to_string(&acct).expect("Failed to serialize");
+        let restored: Account = serde_json::from_str(&json).expect("Failed to deserialize");
+        restored.validate_invariants().unwrap();
+
+        assert_eq!(restored, acct);
+        check_account(&restored, 75, 100, false);
+        check_deposit(&restored, 1, true);
+        check_deposit(&restored, 2, false);
+        assert!(restored.deposits[&2].is_resolved());
+        assert_eq!(restored.deposit_description(3), Some("INV-1042"));
+    }
+
+    #[test]
+    fn as_decimal() {
+        let acct = Account::new_empty();
+        assert_eq!(acct.available_as_decimal(), "0.0000");
+        assert_eq!(acct.held_as_decimal(), "0.0000");
+        assert_eq!(acct.total_as_decimal(), "0.0000");
+
+        let mut acct = Account::new(1, 20_000).unwrap();
+        acct.deposit(2, 10_050, None).expect("Failed to deposit");
+        assert_eq!(acct.available_as_decimal(), "3.0050");
+
+        acct.dispute(1).expect("Failed to dispute");
+        assert_eq!(acct.available_as_decimal(), "1.0050");
+        assert_eq!(acct.held_as_decimal(), "2.0000");
+        assert_eq!(acct.total_as_decimal(), "3.0050");
+    }
+
     #[test]
     fn double_dispute() {
-        let mut acct = Account::new(1, 100);
+        let mut acct = Account::new(1, 100).unwrap();
         acct.dispute(1).unwrap();
+        acct.validate_invariants().unwrap();
         let result = acct.dispute(1);
+        acct.validate_invariants().unwrap();
 
         assert!(matches!(
             result,
@@ -282,15 +1261,258 @@ mod tests {
         check_deposit(&acct, 1, true);
     }
 
+    #[test]
+    fn deposit_timeline() {
+        let mut acct = Account::new_empty();
+        assert!(acct.deposit_timeline().is_empty());
+
+        for (tx, amt) in [(7, 700), (2, 200), (9, 900), (4, 400)] {
+            acct.deposit(tx, amt, None).unwrap();
+        }
+        acct.dispute(9).unwrap();
+
+        assert_eq!(
+            acct.deposit_timeline(),
+            [
+                (2, 200, false),
+                (4, 400, false),
+                (7, 700, false),
+                (9, 900, true)
+            ]
+        );
+    }
+
+    #[test]
+    fn export_history() {
+        let mut acct = Account::new_empty();
+        let mut buf = Vec::new();
+        acct.export_history(&mut buf).expect("Failed to export");
+        assert_eq!(buf, b"tx,amount,disputed,chargebacked\n");
+
+        acct.deposit(3, 15000, None).unwrap();
+        acct.deposit(1, 100, None).unwrap();
+        acct.deposit(2, 25000, None).unwrap();
+        acct.dispute(2).unwrap();
+        acct.dispute(1).unwrap();
+        acct.chargeback(1).unwrap();
+
+        let mut buf = Vec::new();
+        acct.export_history(&mut buf).expect("Failed to export");
+        assert_eq!(
+            std::str::from_utf8(&buf).expect("Failed to convert"),
+            "tx,amount,disputed,chargebacked\n\
+             1,0.0100,false,true\n\
+             2,2.5000,true,false\n\
+             3,1.5000,false,false\n"
+        );
+    }
+
     #[test]
     fn locked_account() {
-        let mut acct = Account::new(1, 100);
+        let mut acct = Account::new(1, 100).unwrap();
         acct.dispute(1).unwrap();
+        acct.validate_invariants().unwrap();
         acct.chargeback(1).unwrap();
-        let result = acct.deposit(2, 50);
+        acct.validate_invariants().unwrap();
+        let result = acct.deposit(2, 50, None);
+        acct.validate_invariants().unwrap();
 
         assert!(matches!(result, Err(TransactorError::FrozenAccount)));
         check_account(&acct, 0, 0, true);
         check_deposit(&acct, 1, false);
     }
+
+    #[test]
+    fn invariant_violation() {
+        let mut acct = Account::new(1, 100).unwrap();
+        acct.held = FixedPoint::from_raw(50);
+
+        assert_eq!(
+            acct.validate_invariants(),
+            Err("held funds of 50 do not match disputed deposits of 0".to_string())
+        );
+
+        acct.dispute(1).unwrap();
+        acct.available = FixedPoint::from_raw(u64::MAX);
+
+        assert!(acct.validate_invariants().is_err());
+
+        let mut acct = Account::new(1, 100).unwrap();
+        acct.dispute(1).unwrap();
+        acct.chargeback(1).unwrap();
+        acct.deposits.get_mut(&1).unwrap().resolved = true;
+
+        assert_eq!(
+            acct.validate_invariants(),
+            Err("charged back deposit 1 is disputed or resolved".to_string())
+        );
+    }
+
+    #[test]
+    fn chargeback_after_resolve() {
+        let mut acct = Account::new(1, 100).unwrap();
+        acct.dispute(1).unwrap();
+        acct.resolve(1).unwrap();
+        acct.dispute(1).unwrap();
+        acct.chargeback(1).unwrap();
+        acct.validate_invariants().unwrap();
+
+        let deposit = &acct.deposits[&1];
+        assert!(deposit.is_chargebacked());
+        assert!(!deposit.is_resolved());
+        assert_eq!(acct.resolved_count(), 0);
+
+        // Unfreezing does not allow the deposit to be disputed again
+        acct.set_frozen(false);
+        assert!(matches!(
+            acct.dispute(1),
+            Err(TransactorError::ChargebackedTxn(1))
+        ));
+        acct.validate_invariants().unwrap();
+        check_account(&acct, 0, 0, false);
+    }
+
+    #[test]
+    fn can_be_closed() {
+        assert!(Account::new_empty().can_be_closed());
+
+        let mut acct = Account::new(1, 100).unwrap();
+        assert!(!acct.can_be_closed());
+        acct.withdraw(2, 100).unwrap();
+        assert!(acct.can_be_closed());
+
+        acct.set_frozen(true);
+        assert!(!acct.can_be_closed());
+    }
+
+    #[test]
+    fn balance_overflow() {
+        let mut acct = Account::new(1, u64::MAX - 10).unwrap();
+        assert!(matches!(
+            acct.deposit(2, 20, None),
+            Err(TransactorError::BalanceOverflow { tx: 2 })
+        ));
+        assert!(!acct.has_transaction(2));
+
+        // Held funds count towards the total that must stay representable
+        acct.dispute(1).unwrap();
+        assert!(matches!(
+            acct.deposit(2, 20, None),
+            Err(TransactorError::BalanceOverflow { tx: 2 })
+        ));
+        acct.deposit(2, 10, None).unwrap();
+        acct.validate_invariants().unwrap();
+        check_account(&acct, 10, u64::MAX - 10, false);
+
+        // A corrupted held balance cannot overflow on dispute
+        let mut acct = Account::new(1, 100).unwrap();
+        acct.held = FixedPoint::from_raw(u64::MAX);
+        assert!(matches!(
+            acct.dispute(1),
+            Err(TransactorError::BalanceOverflow { tx: 1 })
+        ));
+        assert_eq!(acct.available(), 100);
+    }
+
+    #[test]
+    fn held_underflow() {
+        let mut acct = Account::new(1, 100).unwrap();
+        acct.dispute(1).unwrap();
+        acct.held = FixedPoint::from_raw(40);
+
+        assert!(matches!(
+            acct.resolve(1),
+            Err(TransactorError::HeldUnderflow { tx: 1 })
+        ));
+        assert!(matches!(
+            acct.chargeback(1),
+            Err(TransactorError::HeldUnderflow { tx: 1 })
+        ));
+
+        // The refused operations leave the account and deposit unchanged
+        check_account(&acct, 0, 40, false);
+        check_deposit(&acct, 1, true);
+        assert_eq!(acct.age_in_transactions(), 2);
+    }
+
+    #[test]
+    fn available_underflow() {
+        let mut acct = Account::new(1, 100).unwrap();
+        acct.deposit(2, 50, None).unwrap();
+        acct.available = FixedPoint::from_raw(30);
+
+        assert!(matches!(
+            acct.withdraw(3, 40),
+            Err(TransactorError::WithdrawalExceedsAvailable {
+                available: 30,
+                attempted: 40
+            })
+        ));
+        assert!(matches!(
+            acct.dispute(2),
+            Err(TransactorError::DisputeExceedsAvailable {
+                available: 30,
+                attempted: 50
+            })
+        ));
+        assert!(matches!(
+            acct.apply_fee(40, false),
+            Err(TransactorError::InsufficientFundsForFee {
+                available: 30,
+                attempted: 40
+            })
+        ));
+
+        check_account(&acct, 30, 0, false);
+        check_deposit(&acct, 2, false);
+    }
+
+    /// An operation applied to an `Account` in property tests.
+    #[derive(Debug, Clone)]
+    enum Op {
+        Deposit { tx: u32, amount: u64 },
+        Withdraw { tx: u32, amount: u64 },
+        Dispute { tx: u32 },
+        Resolve { tx: u32 },
+        Chargeback { tx: u32 },
+    }
+
+    /// Generate operations over a small range of transaction IDs so operations target
+    /// previously seen transactions.
+    fn op_strategy() -> impl Strategy<Value = Op> {
+        let tx = 0..16u32;
+        let amount = 0..1_000_000u64;
+
+        prop_oneof![
+            4 => (tx.clone(), amount.clone()).prop_map(|(tx, amount)| Op::Deposit { tx, amount }),
+            3 => (tx.clone(), amount.clone()).prop_map(|(tx, amount)| Op::Withdraw { tx, amount }),
+            3 => tx.clone().prop_map(|tx| Op::Dispute { tx }),
+            3 => tx.clone().prop_map(|tx| Op::Resolve { tx }),
+            1 => tx.prop_map(|tx| Op::Chargeback { tx }),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn arbitrary_operations(
+            initial in 1..1_000_000u64,
+            ops in prop::collection::vec(op_strategy(), 0..64),
+        ) {
+            let mut acct = Account::new(0, initial).unwrap();
+
+            for op in ops {
+                let result = match op {
+                    Op::Deposit { tx, amount } if acct.can_deposit(tx) => acct.deposit(tx, amount, None),
+                    Op::Withdraw { tx, amount } if acct.can_withdraw(tx, amount) => acct.withdraw(tx, amount),
+                    Op::Dispute { tx } if acct.can_dispute(tx) => acct.dispute(tx),
+                    Op::Resolve { tx } if acct.can_resolve(tx) => acct.resolve(tx),
+                    Op::Chargeback { tx } if acct.can_chargeback(tx) => acct.chargeback(tx),
+                    _ => continue,
+                };
+
+                prop_assert!(result.is_ok(), "{:?} failed: {:?}", op, result);
+                prop_assert_eq!(acct.validate_invariants(), Ok(()));
+            }
+        }
+    }
 }
@@ -0,0 +1,13 @@
+use crate::{error::TransactorError, operation::Operation};
+
+/// A record of an attempted operation and its outcome, including rejected operations.
+#[derive(Debug, Clone)]
+pub struct AuditEntry {
+    /// The row of the input the operation was read from.
+    pub row: u64,
+    pub operation: Operation,
+    pub client: u16,
+    pub tx: Option<u32>,
+    pub amount: Option<u64>,
+    pub outcome: Result<(), TransactorError>,
+}
@@ -0,0 +1,212 @@
+//! An account manager that may be shared between threads, locking only the shard of the affected account.
+
+use crate::{
+    account::Account,
+    config::Config,
+    error::TransactorError,
+    manager::{check_amount, deposit_limited, open_account, Manager},
+};
+use dashmap::{mapref::entry::Entry, DashMap};
+
+/// Account manager associating a client ID to an account, with every operation taking `&self`.
+/// Accounts are held in a `DashMap`, so operations on accounts in different shards proceed concurrently,
+/// while each operation on a single account is applied atomically.
+/// Unlike `Manager`, accounts are not kept in the order they were created.
+#[derive(Debug, Default)]
+pub struct ConcurrentManager {
+    accounts: DashMap<u16, Account>,
+    max_deposits: Option<usize>,
+    max_amount: Option<u64>,
+}
+
+impl ConcurrentManager {
+    /// Construct a new `ConcurrentManager`.
+    #[inline]
+    pub fn new() -> Self {
+        ConcurrentManager::default()
+    }
+
+    /// Construct a new `ConcurrentManager` with the deposit and withdrawal limits of the `Config`.
+    #[inline]
+    pub fn from_config(config: &Config) -> Self {
+        ConcurrentManager {
+            accounts: DashMap::new(),
+            max_deposits: config.max_deposits_per_account,
+            max_amount: config.max_transaction_amount,
+        }
+    }
+
+    /// Get a copy of the account specified by the client ID.
+    #[inline]
+    pub fn account(&self, client: u16) -> Option<Account> {
+        self.accounts.get(&client).map(|acct| acct.clone())
+    }
+
+    /// Get the number of accounts.
+    #[inline]
+    pub fn account_count(&self) -> usize {
+        self.accounts.len()
+    }
+
+    /// Return whether there are no accounts.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+    }
+
+    /// Apply the operation to the account specified by the client ID, holding the lock of its shard.
+    #[inline]
+    fn with_account<F>(&self, client: u16, f: F) -> Result<(), TransactorError>
+    where
+        F: FnOnce(&mut Account) -> Result<(), TransactorError>,
+    {
+        let mut acct = self
+            .accounts
+            .get_mut(&client)
+            .ok_or(TransactorError::NoClient(client))?;
+        f(&mut acct)
+    }
+
+    /// Deposit funds into the account specified by the client ID, optionally tagged with a description.
+    /// A new account is only created if the deposit succeeds.
+    /// Deposits are limited as in `Manager::deposit`.
+    #[inline]
+    pub fn deposit(
+        &self,
+        client: u16,
+        tx: u32,
+        amt: u64,
+        description: Option<String>,
+    ) -> Result<(), TransactorError> {
+        let (limit, max) = (self.max_deposits, self.max_amount);
+
+        match self.accounts.entry(client) {
+            Entry::Occupied(mut entry) => {
+                deposit_limited(entry.get_mut(), client, tx, amt, description, limit, max)
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(open_account(client, tx, amt, description, limit, max)?);
+                Ok(())
+            }
+        }
+    }
+
+    /// Withdraw funds from the account specified by the client ID, tracking the withdrawal by its transaction ID.
+    /// Withdrawals are limited as in `Manager::withdraw`.
+    #[inline]
+    pub fn withdraw(&self, client: u16, tx: u32, amt: u64) -> Result<(), TransactorError> {
+        check_amount(amt, self.max_amount)?;
+        self.with_account(client, |acct| acct.withdraw(tx, amt))
+    }
+
+    /// Dispute a transaction according to the client and transaction ID pair.
+    #[inline]
+    pub fn dispute(&self, client: u16, tx: u32) -> Result<(), TransactorError> {
+        self.with_account(client, |acct| acct.dispute(tx))
+    }
+
+    /// Resolve a dispute according to the client and transaction ID pair.
+    #[inline]
+    pub fn resolve(&self, client: u16, tx: u32) -> Result<(), TransactorError> {
+        self.with_account(client, |acct| acct.resolve(tx))
+    }
+
+    /// Chargeback a disputed transaction according to the client and transaction ID pair.
+    #[inline]
+    pub fn chargeback(&self, client: u16, tx: u32) -> Result<(), TransactorError> {
+        self.with_account(client, |acct| acct.chargeback(tx))
+    }
+
+    /// Consume the `ConcurrentManager`, moving every account into a `Manager` in no particular order.
+    #[inline]
+    pub fn into_manager(self) -> Manager {
+        self.accounts.into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ConcurrentManager;
+    use crate::{config::Config, error::TransactorError};
+
+    #[test]
+    fn deposit_withdraw() {
+        let mgr = ConcurrentManager::new();
+        mgr.deposit(1, 1, 100, None).expect("Failed to deposit");
+        mgr.withdraw(1, 2, 40).expect("Failed to withdraw");
+
+        assert_eq!(mgr.account(1).expect("No Account").available(), 60);
+        assert!(matches!(
+            mgr.withdraw(2, 3, 40),
+            Err(TransactorError::NoClient(2))
+        ));
+        assert!(matches!(
+            mgr.deposit(1, 1, 100, None),
+            Err(TransactorError::DuplicateTxn(1))
+        ));
+        assert_eq!(mgr.account_count(), 1);
+    }
+
+    #[test]
+    fn dispute_chargeback() {
+        let mgr = ConcurrentManager::new();
+        mgr.deposit(1, 1, 100, None).expect("Failed to deposit");
+        mgr.deposit(1, 2, 50, None).expect("Failed to deposit");
+        mgr.dispute(1, 1).expect("Failed to dispute");
+        mgr.dispute(1, 2).expect("Failed to dispute");
+        mgr.resolve(1, 2).expect("Failed to resolve");
+        mgr.chargeback(1, 1).expect("Failed to chargeback");
+
+        let manager = mgr.into_manager();
+        let (client, acct) = manager.into_iter().next().expect("No Account");
+        assert_eq!(client, 1);
+        assert_eq!(acct.available(), 50);
+        assert_eq!(acct.held(), 0);
+        assert!(acct.is_frozen());
+    }
+
+    #[test]
+    fn deposit_limits() {
+        let config = Config {
+            max_deposits_per_account: Some(2),
+            max_transaction_amount: Some(1000),
+            ..Config::default()
+        };
+        let mgr = ConcurrentManager::from_config(&config);
+
+        assert!(matches!(
+            mgr.deposit(1, 1, 0, None),
+            Err(TransactorError::ZeroAmount)
+        ));
+        assert!(matches!(
+            mgr.deposit(1, u32::MAX, 100, None),
+            Err(TransactorError::TxIdSpaceExhausted)
+        ));
+        assert!(matches!(
+            mgr.deposit(1, 1, 1001, None),
+            Err(TransactorError::AmountTooLarge {
+                amount: 1001,
+                max: 1000
+            })
+        ));
+        assert!(mgr.is_empty());
+
+        mgr.deposit(1, 1, 100, None).expect("Failed to deposit");
+        mgr.deposit(1, 2, 100, None).expect("Failed to deposit");
+        assert!(matches!(
+            mgr.deposit(1, 3, 100, None),
+            Err(TransactorError::DepositLimitExceeded {
+                client: 1,
+                limit: 2
+            })
+        ));
+        assert!(matches!(
+            mgr.withdraw(1, 3, 1001),
+            Err(TransactorError::AmountTooLarge {
+                amount: 1001,
+                max: 1000
+            })
+        ));
+        assert_eq!(mgr.account(1).expect("No Account").available(), 200);
+    }
+}
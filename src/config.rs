@@ -0,0 +1,319 @@
+//! Runtime configuration, set from command line flags or a TOML configuration file.
+//!
+//! The configuration file keys mirror the long command line flag names,
+//! and every key is optional:
+//!
+//! ```toml
+//! strict = true
+//! verbosity = "quiet"          # "quiet", "normal", or "verbose"
+//! sort-output = true
+//! format = "ndjson"            # "csv" or "ndjson"
+//! input-format = "csv"        # "csv" or "ndjson"
+//! audit-log = "audit.csv"
+//! client = [1, 2]
+//! gzip = false
+//! delimiter = "\t"
+//! output-delimiter = "|"
+//! no-header = false
+//! fee-on-frozen = false
+//! ignore-frozen = false
+//! max-deposits = 10000
+//! max-ops-per-client = 1000
+//! skip-lines = 0
+//! precision = 4                # 2, 4, 6, or 8 decimal places
+//! undo-depth = 0
+//! ```
+
+use crate::{error::TransactorError, fixed_point::SCALE};
+use serde::{Deserialize, Deserializer};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The level of diagnostic output written to stderr.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Verbosity {
+    /// Only fatal errors are reported.
+    Quiet,
+
+    /// Soft errors are reported as they are encountered.
+    #[default]
+    Normal,
+
+    /// The outcome of every record is reported, followed by a summary.
+    Verbose,
+}
+
+/// The format accounts are written in.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// One CSV row per account, preceded by a header row.
+    #[default]
+    Csv,
+
+    /// One JSON object per line for each account.
+    Ndjson,
+}
+
+/// The format transactions are read in.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InputFormat {
+    /// CSV rows, optionally preceded by a header row.
+    #[default]
+    Csv,
+
+    /// One JSON object per line for each transaction.
+    Ndjson,
+}
+
+/// Runtime configuration controlling how transactions are processed and reported.
+#[derive(Debug, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Config {
+    /// Whether soft errors halt processing as if they were fatal.
+    pub strict: bool,
+
+    /// The level of diagnostic output.
+    pub verbosity: Verbosity,
+
+    /// The number of fixed point units in one whole unit of an amount, a power of ten.
+    /// Amounts are parsed and written with one decimal place per power, 4 by default.
+    #[serde(rename = "precision", deserialize_with = "scale_from_precision")]
+    pub decimal_scale: u64,
+
+    /// Whether accounts are written in ascending order of client ID.
+    pub sort_output: bool,
+
+    /// The format accounts are written in.
+    #[serde(rename = "format")]
+    pub output_format: OutputFormat,
+
+    /// The format transactions are read in.
+    pub input_format: InputFormat,
+
+    /// The file the audit log is exported to.
+    /// The audit log is only recorded when this is set.
+    pub audit_log: Option<PathBuf>,
+
+    /// The clients to include in the output. All clients are included when empty.
+    #[serde(rename = "client")]
+    pub clients: Vec<u16>,
+
+    /// Whether the input and output are gzip compressed.
+    /// Only supported when built with the `gzip` feature.
+    pub gzip: bool,
+
+    /// The field delimiter of the input.
+    #[serde(deserialize_with = "delimiter_from_str")]
+    pub delimiter: u8,
+
+    /// The field delimiter of the output. The input delimiter is used when absent.
+    #[serde(deserialize_with = "optional_delimiter_from_str")]
+    pub output_delimiter: Option<u8>,
+
+    /// The number of lines of preamble skipped before the input header row.
+    pub skip_lines: usize,
+
+    /// Whether the input and output omit the header row.
+    /// Input columns are then mapped by position: type, client, tx and amount.
+    pub no_header: bool,
+
+    /// Whether fees are charged to frozen accounts.
+    pub fee_on_frozen: bool,
+
+    /// Whether operations refused because the account is frozen are skipped without any diagnostic output.
+    pub ignore_frozen: bool,
+
+    /// The maximum number of deposits tracked per account.
+    /// Deposits beyond the limit are refused. Unlimited when absent.
+    #[serde(rename = "max-deposits")]
+    pub max_deposits_per_account: Option<usize>,
+
+    /// The maximum number of operations applied per client in a processing run.
+    /// Operations beyond the limit are refused. Unlimited when absent.
+    pub max_ops_per_client: Option<usize>,
+
+    /// The number of operations that may be undone. Undo is disabled when 0.
+    pub undo_depth: usize,
+
+    /// The maximum amount of a single deposit or withdrawal, scaled to the configured precision.
+    /// Larger amounts are refused. Unlimited when absent.
+    /// Only set by the `--max-amount` flag, as the scale depends on the final precision.
+    #[serde(skip)]
+    pub max_transaction_amount: Option<u64>,
+}
+
+impl Config {
+    /// Parse a `Config` from the contents of a TOML configuration file.
+    pub fn from_toml(s: &str) -> Result<Self, TransactorError> {
+        toml::from_str(s).map_err(|error| TransactorError::ConfigError(error.to_string()))
+    }
+
+    /// Read and parse a TOML configuration file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, TransactorError> {
+        Config::from_toml(&fs::read_to_string(path)?)
+    }
+
+    /// The number of decimal places amounts are parsed and written with.
+    #[inline]
+    pub fn precision(&self) -> usize {
+        self.decimal_scale.ilog10() as usize
+    }
+
+    /// The field delimiter used when writing output.
+    #[inline]
+    pub fn output_delimiter(&self) -> u8 {
+        self.output_delimiter.unwrap_or(self.delimiter)
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            strict: false,
+            verbosity: Verbosity::default(),
+            decimal_scale: SCALE,
+            sort_output: false,
+            output_format: OutputFormat::default(),
+            input_format: InputFormat::default(),
+            audit_log: None,
+            clients: Vec::new(),
+            gzip: false,
+            delimiter: b',',
+            output_delimiter: None,
+            skip_lines: 0,
+            no_header: false,
+            fee_on_frozen: false,
+            ignore_frozen: false,
+            max_deposits_per_account: None,
+            max_ops_per_client: None,
+            undo_depth: 0,
+            max_transaction_amount: None,
+        }
+    }
+}
+
+/// Parse a field delimiter, which must be a single non-whitespace ASCII character or a tab.
+/// The escape sequence `\t` is accepted as a tab.
+pub fn parse_delimiter(arg: &str) -> Result<u8, String> {
+    match arg.as_bytes() {
+        b"\\t" | b"\t" => Ok(b'\t'),
+        &[byte] if byte.is_ascii() && !byte.is_ascii_whitespace() => Ok(byte),
+        _ => Err(format!("Invalid delimiter {arg:?}")),
+    }
+}
+
+/// Parse a number of decimal places, which must be 2, 4, 6, or 8, into the scale factor.
+pub fn parse_precision(arg: &str) -> Result<u64, String> {
+    match arg.parse() {
+        Ok(precision @ (2 | 4 | 6 | 8)) => Ok(10u64.pow(precision)),
+        _ => Err(format!("Invalid precision {arg:?}, expected 2, 4, 6, or 8")),
+    }
+}
+
+/// Deserialize a number of decimal places into the scale factor.
+#[inline]
+fn scale_from_precision<'de, D>(d: D) -> Result<u64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let precision = u32::deserialize(d)?;
+    parse_precision(&precision.to_string()).map_err(serde::de::Error::custom)
+}
+
+/// Deserialize a field delimiter from a string.
+#[inline]
+fn delimiter_from_str<'de, D>(d: D) -> Result<u8, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value: String = Deserialize::deserialize(d)?;
+    parse_delimiter(&value).map_err(serde::de::Error::custom)
+}
+
+/// Deserialize an optional field delimiter from a string.
+#[inline]
+fn optional_delimiter_from_str<'de, D>(d: D) -> Result<Option<u8>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    delimiter_from_str(d).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Config, InputFormat, OutputFormat, Verbosity};
+    use crate::error::TransactorError;
+    use std::{fs, path::PathBuf};
+
+    #[test]
+    fn load_toml() {
+        let toml = r#"
+            strict = true
+            verbosity = "quiet"
+            sort-output = true
+            format = "ndjson"
+            input-format = "ndjson"
+            audit-log = "audit.csv"
+            client = [1, 2]
+            delimiter = "\\t"
+            output-delimiter = "|"
+            fee-on-frozen = true
+            ignore-frozen = true
+            max-deposits = 10000
+            max-ops-per-client = 1000
+            skip-lines = 2
+        "#;
+        let path = std::env::temp_dir().join("transactor_load_toml.toml");
+        fs::write(&path, toml).expect("Failed to write config");
+
+        let config = Config::load(&path).expect("Failed to load config");
+
+        assert!(config.strict);
+        assert_eq!(config.verbosity, Verbosity::Quiet);
+        assert!(config.sort_output);
+        assert_eq!(config.output_format, OutputFormat::Ndjson);
+        assert_eq!(config.input_format, InputFormat::Ndjson);
+        assert_eq!(config.audit_log, Some(PathBuf::from("audit.csv")));
+        assert_eq!(config.clients, [1, 2]);
+        assert_eq!(config.delimiter, b'\t');
+        assert_eq!(config.output_delimiter(), b'|');
+        assert!(config.fee_on_frozen);
+        assert!(config.ignore_frozen);
+        assert_eq!(config.max_deposits_per_account, Some(10000));
+        assert_eq!(config.max_ops_per_client, Some(1000));
+        assert_eq!(config.skip_lines, 2);
+
+        // Absent keys keep their default values
+        assert_eq!(config.decimal_scale, 10_000);
+        assert!(!config.gzip);
+        assert!(!config.no_header);
+    }
+
+    #[test]
+    fn precision() {
+        let config = Config::from_toml("precision = 2").expect("Failed to parse config");
+        assert_eq!(config.decimal_scale, 100);
+        assert_eq!(config.precision(), 2);
+        assert_eq!(Config::default().precision(), 4);
+    }
+
+    #[test]
+    fn invalid_toml() {
+        for toml in [
+            "unknown = true",
+            "delimiter = \";;\"",
+            "format = \"xml\"",
+            "precision = 3",
+        ] {
+            assert!(matches!(
+                Config::from_toml(toml),
+                Err(TransactorError::ConfigError(_))
+            ));
+        }
+    }
+}
@@ -1,43 +1,250 @@
-use std::{error, fmt, io};
+use crate::fixed_point::FixedPointError;
+use serde::{Serialize, Serializer};
+use std::{error, fmt, io, sync::Arc};
 
-#[derive(Debug)]
+/// Serialized with the variant name as `type` and any fields as `data`, such as
+/// `{"type":"NoClient","data":3}`. Wrapped library errors are serialized as their message.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", content = "data")]
 pub enum TransactorError {
     /// A failure during parsing.
-    ParseError(csv::Error),
+    ParseError(#[serde(serialize_with = "serialize_display")] Arc<csv::Error>),
+
+    /// A failure during parsing of a specific row of the input.
+    ParseErrorAt {
+        row: u64,
+        #[serde(serialize_with = "serialize_display")]
+        source: Arc<csv::Error>,
+    },
+
+    /// A failure during parsing of a specific line of newline delimited JSON input.
+    JsonParseErrorAt {
+        row: u64,
+        #[serde(serialize_with = "serialize_display")]
+        source: Arc<serde_json::Error>,
+    },
 
     /// IO error occured while reading from a file or writing
     /// results to stdout.
-    IoError(io::Error),
+    IoError(#[serde(serialize_with = "serialize_display")] Arc<io::Error>),
+
+    /// A failure while saving or restoring a snapshot of the account state.
+    SnapshotError(String),
+
+    /// A failure while parsing a configuration file.
+    ConfigError(String),
+
+    /// An amount could not be parsed as a fixed point decimal.
+    AmountParseError(#[serde(serialize_with = "serialize_display")] FixedPointError),
+
+    /// The operation name does not match a known operation.
+    UnknownOperation(String),
 
     /// A deposit or withdrawal transaction omitted the amount
     MissingAmount,
 
+    /// An operation other than registration omitted the transaction ID.
+    MissingTxn,
+
+    /// A transfer transaction omitted the target client.
+    MissingTarget,
+
+    /// A deposit or withdrawal transaction has an amount of zero.
+    ZeroAmount,
+
+    /// A dispute, resolve, or chargeback transaction included an amount.
+    UnexpectedAmount,
+
+    /// A transaction has a client ID of zero.
+    ZeroClient,
+
+    /// A transaction has a transaction ID of zero.
+    ZeroTxn,
+
     /// A withdrawal exceeds the available funds in the account.
     WithdrawalExceedsAvailable { available: u64, attempted: u64 },
 
     /// A dispute exceeds the available funds in the account.
     DisputeExceedsAvailable { available: u64, attempted: u64 },
 
+    /// A deposit or withdrawal exceeds the maximum amount of a single transaction.
+    AmountTooLarge { amount: u64, max: u64 },
+
+    /// A fee exceeds the available funds in the account.
+    InsufficientFundsForFee { available: u64, attempted: u64 },
+
     /// The account is frozen, no further actions may effect it.
     FrozenAccount,
 
     /// The client ID does not match an active account.
     NoClient(u16),
 
+    /// Attempt to register a client ID that already has an account,
+    /// or to merge a client ID present in both managers.
+    ClientAlreadyExists(u16),
+
+    /// Attempt to close an account that still holds funds, has disputed deposits, or is frozen.
+    AccountNotCloseable { reason: String },
+
     /// The transaction ID does not match a previous transaction.
     NoTransaction(u32),
 
     /// A deposit or withdrawal transaction duplicated a transaction ID
     DuplicateTxn(u32),
 
+    /// A deposit used the largest transaction ID, leaving no transaction IDs after it.
+    TxIdSpaceExhausted,
+
+    /// A deposit would exceed the maximum number of deposits tracked for the account.
+    DepositLimitExceeded { client: u16, limit: usize },
+
+    /// A client exceeded the maximum number of operations per processing run.
+    ClientRateLimited(u16),
+
     /// A resolve or chargeback action attempted on an non disputed transaction.
     NonDisputedTxn(u32),
 
     /// Attempt to dispute an already disputed transaction.
     AlreadyDisputedTxn(u32),
+
+    /// Attempt to dispute a deposit that has already been charged back.
+    ChargebackedTxn(u32),
+
+    /// Attempt to remove a deposit that has not been charged back.
+    DepositNotChargeable(u32),
+
+    /// A credit would overflow the funds of the account.
+    BalanceOverflow { tx: u32 },
+
+    /// Releasing a disputed deposit would take more than the held funds of the account,
+    /// which is only possible if the account state is inconsistent.
+    HeldUnderflow { tx: u32 },
+
+    /// There are no operations left to undo.
+    NothingToUndo,
+
+    /// An operation panicked while holding the lock of a shared `Manager`, which may be partially updated.
+    LockPoisoned,
+
+    /// The name does not match a saved checkpoint.
+    NoCheckpoint(String),
+
+    /// The named operation replaces accounts wholesale, so it is refused while recording a transaction log.
+    NotReplayable(String),
+
+    /// A transfer names the same client as its source and target.
+    SelfTransfer(u16),
+
+    /// A transfer between two clients was refused, leaving both accounts unchanged.
+    TransferFailed {
+        from: u16,
+        to: u16,
+        reason: Box<TransactorError>,
+    },
 }
 
 impl TransactorError {
+    /// Construct a ParseErrorAt error if the position of the failure is known,
+    /// otherwise fall back to a ParseError.
+    pub fn parse_error(error: csv::Error) -> Self {
+        match error.position().map(csv::Position::record) {
+            Some(row) => TransactorError::ParseErrorAt {
+                row,
+                source: Arc::new(error),
+            },
+            None => TransactorError::ParseError(Arc::new(error)),
+        }
+    }
+
+    /// Construct a JsonParseErrorAt error for the row of the input.
+    pub fn json_parse_error(row: u64, error: serde_json::Error) -> Self {
+        TransactorError::JsonParseErrorAt {
+            row,
+            source: Arc::new(error),
+        }
+    }
+
+    /// Serialize the error as a JSON object, for structured logging.
+    pub fn to_json_string(&self) -> String {
+        serde_json::to_string(self).expect("Failed to serialize error")
+    }
+
+    /// Return whether the error is fatal and should halt processing.
+    /// All other errors are soft errors, refusing only the offending operation.
+    pub fn is_fatal(&self) -> bool {
+        matches!(
+            self,
+            TransactorError::ParseError(_)
+                | TransactorError::ParseErrorAt { .. }
+                | TransactorError::JsonParseErrorAt { .. }
+                | TransactorError::IoError(_)
+                | TransactorError::SnapshotError(_)
+                | TransactorError::ConfigError(_)
+                | TransactorError::AmountParseError(_)
+                | TransactorError::UnknownOperation(_)
+                | TransactorError::MissingAmount
+                | TransactorError::MissingTxn
+                | TransactorError::MissingTarget
+                | TransactorError::LockPoisoned
+        )
+    }
+
+    /// Return whether the error refused an operation on a frozen account,
+    /// including either side of a transfer.
+    pub fn is_frozen(&self) -> bool {
+        match self {
+            TransactorError::FrozenAccount => true,
+            TransactorError::TransferFailed { reason, .. } => reason.is_frozen(),
+            _ => false,
+        }
+    }
+
+    /// Get the name of the kind of error, matching the variant name.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            TransactorError::ParseError(_) => "ParseError",
+            TransactorError::ParseErrorAt { .. } => "ParseErrorAt",
+            TransactorError::JsonParseErrorAt { .. } => "JsonParseErrorAt",
+            TransactorError::IoError(_) => "IoError",
+            TransactorError::SnapshotError(_) => "SnapshotError",
+            TransactorError::ConfigError(_) => "ConfigError",
+            TransactorError::AmountParseError(_) => "AmountParseError",
+            TransactorError::UnknownOperation(_) => "UnknownOperation",
+            TransactorError::MissingAmount => "MissingAmount",
+            TransactorError::MissingTxn => "MissingTxn",
+            TransactorError::MissingTarget => "MissingTarget",
+            TransactorError::ZeroAmount => "ZeroAmount",
+            TransactorError::UnexpectedAmount => "UnexpectedAmount",
+            TransactorError::ZeroClient => "ZeroClient",
+            TransactorError::ZeroTxn => "ZeroTxn",
+            TransactorError::WithdrawalExceedsAvailable { .. } => "WithdrawalExceedsAvailable",
+            TransactorError::DisputeExceedsAvailable { .. } => "DisputeExceedsAvailable",
+            TransactorError::AmountTooLarge { .. } => "AmountTooLarge",
+            TransactorError::InsufficientFundsForFee { .. } => "InsufficientFundsForFee",
+            TransactorError::FrozenAccount => "FrozenAccount",
+            TransactorError::NoClient(_) => "NoClient",
+            TransactorError::ClientAlreadyExists(_) => "ClientAlreadyExists",
+            TransactorError::AccountNotCloseable { .. } => "AccountNotCloseable",
+            TransactorError::NoTransaction(_) => "NoTransaction",
+            TransactorError::DuplicateTxn(_) => "DuplicateTxn",
+            TransactorError::TxIdSpaceExhausted => "TxIdSpaceExhausted",
+            TransactorError::DepositLimitExceeded { .. } => "DepositLimitExceeded",
+            TransactorError::ClientRateLimited(_) => "ClientRateLimited",
+            TransactorError::NonDisputedTxn(_) => "NonDisputedTxn",
+            TransactorError::AlreadyDisputedTxn(_) => "AlreadyDisputedTxn",
+            TransactorError::ChargebackedTxn(_) => "ChargebackedTxn",
+            TransactorError::DepositNotChargeable(_) => "DepositNotChargeable",
+            TransactorError::BalanceOverflow { .. } => "BalanceOverflow",
+            TransactorError::HeldUnderflow { .. } => "HeldUnderflow",
+            TransactorError::NothingToUndo => "NothingToUndo",
+            TransactorError::LockPoisoned => "LockPoisoned",
+            TransactorError::NoCheckpoint(_) => "NoCheckpoint",
+            TransactorError::NotReplayable(_) => "NotReplayable",
+            TransactorError::SelfTransfer(_) => "SelfTransfer",
+            TransactorError::TransferFailed { .. } => "TransferFailed",
+        }
+    }
+
     /// Construct a WithdrawalExceedsAvailable error.
     pub fn withdrawal_exceeds(available: u64, attempted: u64) -> Self {
         TransactorError::WithdrawalExceedsAvailable {
@@ -53,6 +260,15 @@ impl TransactorError {
             attempted,
         }
     }
+
+    /// Construct a TransferFailed error.
+    pub fn transfer_failed(from: u16, to: u16, reason: TransactorError) -> Self {
+        TransactorError::TransferFailed {
+            from,
+            to,
+            reason: Box::new(reason),
+        }
+    }
 }
 
 impl fmt::Display for TransactorError {
@@ -60,10 +276,38 @@ impl fmt::Display for TransactorError {
         match self {
             TransactorError::IoError(err) => write!(f, "io error: {err}"),
             TransactorError::ParseError(err) => write!(f, "parse error: {err}"),
+            TransactorError::ParseErrorAt { row, source } => {
+                write!(f, "parse error at row {row}: {source}")
+            }
+            TransactorError::JsonParseErrorAt { row, source } => {
+                write!(f, "json parse error at row {row}: {source}")
+            }
+            TransactorError::SnapshotError(err) => write!(f, "snapshot error: {err}"),
+            TransactorError::ConfigError(err) => write!(f, "config error: {err}"),
+            TransactorError::AmountParseError(err) => write!(f, "invalid amount: {err}"),
+            TransactorError::UnknownOperation(name) => write!(f, "unknown operation {name:?}"),
             TransactorError::MissingAmount => write!(
                 f,
                 "missing an amount with a deposit or withdrawal operation"
             ),
+            TransactorError::MissingTxn => {
+                write!(
+                    f,
+                    "missing a transaction id with a non registration operation"
+                )
+            }
+            TransactorError::MissingTarget => {
+                write!(f, "missing a target client with a transfer operation")
+            }
+            TransactorError::ZeroAmount => {
+                write!(f, "amount of zero with a deposit or withdrawal operation")
+            }
+            TransactorError::UnexpectedAmount => write!(
+                f,
+                "unexpected amount with a dispute, resolve, or chargeback operation"
+            ),
+            TransactorError::ZeroClient => write!(f, "client id of zero"),
+            TransactorError::ZeroTxn => write!(f, "transaction id of zero"),
             TransactorError::WithdrawalExceedsAvailable {
                 available,
                 attempted,
@@ -78,36 +322,268 @@ impl fmt::Display for TransactorError {
                 f,
                 "attempt to dispute amount of {attempted} exceeds avaiable funds of {available}"
             ),
+            TransactorError::AmountTooLarge { amount, max } => write!(
+                f,
+                "amount of {amount} exceeds the maximum transaction amount of {max}"
+            ),
+            TransactorError::InsufficientFundsForFee {
+                available,
+                attempted,
+            } => write!(
+                f,
+                "attempt to charge fee of {attempted} exceeds avaiable funds of {available}"
+            ),
             TransactorError::FrozenAccount => write!(f, "account is frozen"),
             TransactorError::NoClient(id) => {
                 write!(f, "client with id {id} does not exist")
             }
+            TransactorError::ClientAlreadyExists(id) => {
+                write!(f, "client with id {id} already exists")
+            }
+            TransactorError::AccountNotCloseable { reason } => {
+                write!(f, "account cannot be closed: {reason}")
+            }
             TransactorError::NoTransaction(id) => {
                 write!(f, "transaction with id {id} does not exist")
             }
             TransactorError::DuplicateTxn(id) => {
                 write!(f, "transaction with id {id} already exists")
             }
+            TransactorError::TxIdSpaceExhausted => {
+                write!(
+                    f,
+                    "transaction id {} exhausts the transaction id space",
+                    u32::MAX
+                )
+            }
+            TransactorError::DepositLimitExceeded { client, limit } => {
+                write!(
+                    f,
+                    "client with id {client} reached the limit of {limit} deposits"
+                )
+            }
+            TransactorError::ClientRateLimited(id) => {
+                write!(f, "client with id {id} exceeded the operation limit")
+            }
             TransactorError::NonDisputedTxn(id) => {
                 write!(f, "transaction with id {id} is not disputed")
             }
             TransactorError::AlreadyDisputedTxn(id) => {
                 write!(f, "transaction with id {id} is already disputed")
             }
+            TransactorError::ChargebackedTxn(id) => {
+                write!(f, "transaction with id {id} has already been charged back")
+            }
+            TransactorError::DepositNotChargeable(id) => {
+                write!(f, "transaction with id {id} has not been charged back")
+            }
+            TransactorError::BalanceOverflow { tx } => {
+                write!(f, "transaction with id {tx} overflows the account funds")
+            }
+            TransactorError::HeldUnderflow { tx } => {
+                write!(f, "transaction with id {tx} exceeds the held funds")
+            }
+            TransactorError::NothingToUndo => write!(f, "no operations to undo"),
+            TransactorError::LockPoisoned => {
+                write!(f, "shared manager is poisoned by a panicked operation")
+            }
+            TransactorError::NoCheckpoint(name) => write!(f, "checkpoint {name:?} does not exist"),
+            TransactorError::NotReplayable(name) => {
+                write!(f, "{name} cannot be recorded in the transaction log")
+            }
+            TransactorError::SelfTransfer(id) => {
+                write!(f, "client with id {id} cannot transfer funds to itself")
+            }
+            TransactorError::TransferFailed { from, to, reason } => {
+                write!(
+                    f,
+                    "transfer from client {from} to client {to} failed: {reason}"
+                )
+            }
         }
     }
 }
 
+/// Serialize a value without a `Serialize` implementation as its message.
+fn serialize_display<T: fmt::Display, S: Serializer>(value: &T, s: S) -> Result<S::Ok, S::Error> {
+    s.collect_str(value)
+}
+
 impl From<io::Error> for TransactorError {
     fn from(error: io::Error) -> Self {
-        TransactorError::IoError(error)
+        TransactorError::IoError(Arc::new(error))
     }
 }
 
 impl From<csv::Error> for TransactorError {
     fn from(error: csv::Error) -> Self {
-        TransactorError::ParseError(error)
+        TransactorError::ParseError(Arc::new(error))
+    }
+}
+
+impl From<FixedPointError> for TransactorError {
+    fn from(error: FixedPointError) -> Self {
+        TransactorError::AmountParseError(error)
     }
 }
 
 impl error::Error for TransactorError {}
+
+#[cfg(test)]
+mod tests {
+    use super::TransactorError;
+    use crate::fixed_point::{FixedPoint, FixedPointError};
+
+    #[test]
+    fn display_client_errors() {
+        assert_eq!(
+            TransactorError::NoClient(7).to_string(),
+            "client with id 7 does not exist"
+        );
+        assert_eq!(
+            TransactorError::ClientAlreadyExists(7).to_string(),
+            "client with id 7 already exists"
+        );
+        assert_eq!(
+            TransactorError::AccountNotCloseable {
+                reason: "account is frozen".to_string()
+            }
+            .to_string(),
+            "account cannot be closed: account is frozen"
+        );
+        assert_eq!(
+            TransactorError::SelfTransfer(1).to_string(),
+            "client with id 1 cannot transfer funds to itself"
+        );
+    }
+
+    #[test]
+    fn display_transaction_errors() {
+        assert_eq!(
+            TransactorError::NoTransaction(3).to_string(),
+            "transaction with id 3 does not exist"
+        );
+        assert_eq!(
+            TransactorError::DuplicateTxn(3).to_string(),
+            "transaction with id 3 already exists"
+        );
+        assert_eq!(
+            TransactorError::NonDisputedTxn(3).to_string(),
+            "transaction with id 3 is not disputed"
+        );
+        assert_eq!(
+            TransactorError::AlreadyDisputedTxn(3).to_string(),
+            "transaction with id 3 is already disputed"
+        );
+        assert_eq!(
+            TransactorError::ChargebackedTxn(3).to_string(),
+            "transaction with id 3 has already been charged back"
+        );
+        assert_eq!(
+            TransactorError::ClientRateLimited(3).to_string(),
+            "client with id 3 exceeded the operation limit"
+        );
+        assert_eq!(
+            TransactorError::BalanceOverflow { tx: 3 }.to_string(),
+            "transaction with id 3 overflows the account funds"
+        );
+        assert_eq!(
+            TransactorError::HeldUnderflow { tx: 3 }.to_string(),
+            "transaction with id 3 exceeds the held funds"
+        );
+    }
+
+    #[test]
+    fn display_not_replayable() {
+        assert_eq!(
+            TransactorError::NotReplayable("merge".to_string()).to_string(),
+            "merge cannot be recorded in the transaction log"
+        );
+    }
+
+    #[test]
+    fn display_transfer_failed() {
+        let error = TransactorError::transfer_failed(1, 2, TransactorError::NoClient(1));
+        assert_eq!(
+            error.to_string(),
+            "transfer from client 1 to client 2 failed: client with id 1 does not exist"
+        );
+    }
+
+    #[test]
+    fn amount_parse_error() {
+        let parse = |s: &str| -> Result<FixedPoint, TransactorError> { Ok(s.parse()?) };
+        let error = parse("-1").unwrap_err();
+
+        assert!(matches!(
+            error,
+            TransactorError::AmountParseError(FixedPointError::NegativeAmount)
+        ));
+        assert!(error.is_fatal());
+        assert_eq!(error.to_string(), "invalid amount: negative amount");
+    }
+
+    #[test]
+    fn to_json_string() {
+        assert_eq!(
+            TransactorError::FrozenAccount.to_json_string(),
+            r#"{"type":"FrozenAccount"}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&TransactorError::NoClient(3)).expect("Failed to serialize"),
+            r#"{"type":"NoClient","data":3}"#
+        );
+        assert_eq!(
+            TransactorError::withdrawal_exceeds(1, 2).to_json_string(),
+            r#"{"type":"WithdrawalExceedsAvailable","data":{"available":1,"attempted":2}}"#
+        );
+        assert_eq!(
+            TransactorError::transfer_failed(1, 2, TransactorError::NoClient(1)).to_json_string(),
+            r#"{"type":"TransferFailed","data":{"from":1,"to":2,"reason":{"type":"NoClient","data":1}}}"#
+        );
+
+        // Wrapped errors are serialized as their message
+        assert_eq!(
+            TransactorError::AmountParseError(FixedPointError::NegativeAmount).to_json_string(),
+            r#"{"type":"AmountParseError","data":"negative amount"}"#
+        );
+        let error = std::io::Error::new(std::io::ErrorKind::NotFound, "missing.csv");
+        assert_eq!(
+            TransactorError::from(error).to_json_string(),
+            r#"{"type":"IoError","data":"missing.csv"}"#
+        );
+    }
+
+    #[test]
+    fn kind() {
+        assert_eq!(TransactorError::NoClient(1).kind(), "NoClient");
+        assert_eq!(TransactorError::MissingAmount.kind(), "MissingAmount");
+        assert_eq!(
+            TransactorError::withdrawal_exceeds(1, 2).kind(),
+            "WithdrawalExceedsAvailable"
+        );
+    }
+
+    #[test]
+    fn is_fatal() {
+        assert!(TransactorError::MissingAmount.is_fatal());
+        assert!(TransactorError::MissingTxn.is_fatal());
+        assert!(TransactorError::MissingTarget.is_fatal());
+        assert!(!TransactorError::ZeroAmount.is_fatal());
+        assert!(!TransactorError::UnexpectedAmount.is_fatal());
+        assert!(!TransactorError::ZeroClient.is_fatal());
+        assert!(!TransactorError::ZeroTxn.is_fatal());
+        assert!(TransactorError::UnknownOperation("refund".to_string()).is_fatal());
+        assert!(!TransactorError::ClientAlreadyExists(1).is_fatal());
+        assert!(!TransactorError::NoClient(1).is_fatal());
+        assert!(!TransactorError::FrozenAccount.is_fatal());
+    }
+
+    #[test]
+    fn is_frozen() {
+        assert!(TransactorError::FrozenAccount.is_frozen());
+        assert!(TransactorError::transfer_failed(1, 2, TransactorError::FrozenAccount).is_frozen());
+        assert!(!TransactorError::transfer_failed(1, 2, TransactorError::NoClient(1)).is_frozen());
+        assert!(!TransactorError::NoClient(1).is_frozen());
+    }
+}
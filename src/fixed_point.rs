@@ -0,0 +1,365 @@
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::{error, fmt, str::FromStr};
+
+/// The number of fixed point units in one whole unit.
+pub const SCALE: u64 = 10_000;
+
+/// The number of decimal places represented, matching the `SCALE`.
+pub const DECIMALS: usize = 4;
+
+/// A non-negative decimal amount, stored as a u64 scaled to the ten thousandths place.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FixedPoint(u64);
+
+/// A failure to parse a decimal string as a `FixedPoint`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FixedPointError {
+    /// The string has no digits.
+    Empty,
+
+    /// The amount is negative.
+    NegativeAmount,
+
+    /// The amount has more decimal places than are represented.
+    TooManyDecimalPlaces { given: usize, max: usize },
+
+    /// A character other than a digit or a single decimal point, at the character position in the string.
+    InvalidCharacter { ch: char, position: usize },
+
+    /// The amount exceeds the largest representable amount.
+    Overflow,
+}
+
+impl fmt::Display for FixedPointError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FixedPointError::Empty => write!(f, "amount is empty"),
+            FixedPointError::NegativeAmount => write!(f, "negative amount"),
+            FixedPointError::TooManyDecimalPlaces { given, max } => write!(
+                f,
+                "amount has {given} decimal places, at most {max} are supported"
+            ),
+            FixedPointError::InvalidCharacter { ch, position } => {
+                write!(
+                    f,
+                    "invalid character {ch:?} at position {position} in amount"
+                )
+            }
+            FixedPointError::Overflow => write!(f, "amount is too large"),
+        }
+    }
+}
+
+impl error::Error for FixedPointError {}
+
+impl FixedPoint {
+    /// The zero amount.
+    pub const ZERO: FixedPoint = FixedPoint(0);
+
+    /// Construct a `FixedPoint` from a value already scaled by `SCALE`.
+    #[inline]
+    pub const fn from_raw(raw: u64) -> Self {
+        FixedPoint(raw)
+    }
+
+    /// Get the value scaled by `SCALE`.
+    #[inline]
+    pub const fn raw(self) -> u64 {
+        self.0
+    }
+
+    /// Add two amounts, returning `None` on overflow.
+    #[inline]
+    pub fn checked_add(self, other: FixedPoint) -> Option<FixedPoint> {
+        self.0.checked_add(other.0).map(FixedPoint)
+    }
+
+    /// Subtract an amount, returning `None` if the result would be negative.
+    #[inline]
+    pub fn checked_sub(self, other: FixedPoint) -> Option<FixedPoint> {
+        self.0.checked_sub(other.0).map(FixedPoint)
+    }
+
+    /// Add two amounts, saturating at the maximum representable amount.
+    #[inline]
+    pub fn saturating_add(self, other: FixedPoint) -> FixedPoint {
+        FixedPoint(self.0.saturating_add(other.0))
+    }
+
+    /// Format the amount as a decimal to the ten thousandths place, such as `1.0050`.
+    #[inline]
+    pub fn to_string_decimal(&self) -> String {
+        self.to_string_with_precision(DECIMALS)
+    }
+
+    /// Format the value as a decimal, interpreting it as scaled to the number of decimal places.
+    #[inline]
+    pub fn to_string_with_precision(&self, precision: usize) -> String {
+        let scale = 10u64.pow(precision as u32);
+        let whole = self.0 / scale;
+
+        if precision == 0 {
+            return whole.to_string();
+        }
+
+        format!("{whole}.{:0precision$}", self.0 % scale)
+    }
+
+    /// Parse a decimal string, ignoring any digits beyond the ten thousandths place without rounding.
+    #[inline]
+    pub fn from_str_truncated(s: &str) -> Result<Self, FixedPointError> {
+        FixedPoint::parse(s, DECIMALS, true)
+    }
+
+    /// Parse a decimal string into a value scaled to the number of decimal places,
+    /// ignoring any further digits without rounding.
+    #[inline]
+    pub fn from_str_with_precision(s: &str, precision: usize) -> Result<Self, FixedPointError> {
+        FixedPoint::parse(s, precision, true)
+    }
+
+    /// Parse a decimal string of digits with an optional fractional part, without any floating point arithmetic.
+    /// Digits beyond the number of decimal places are refused unless truncated.
+    fn parse(s: &str, precision: usize, truncate: bool) -> Result<Self, FixedPointError> {
+        if s.starts_with('-') {
+            return Err(FixedPointError::NegativeAmount);
+        }
+
+        let offset = usize::from(s.starts_with('+'));
+        let digits = &s[offset..];
+        let mut point = false;
+
+        for (position, ch) in digits.chars().enumerate() {
+            match ch {
+                '0'..='9' => {}
+                '.' if !point => point = true,
+                _ => {
+                    return Err(FixedPointError::InvalidCharacter {
+                        ch,
+                        position: position + offset,
+                    })
+                }
+            }
+        }
+
+        let (whole, fract) = digits.split_once('.').unwrap_or((digits, ""));
+
+        if whole.is_empty() && fract.is_empty() {
+            return Err(FixedPointError::Empty);
+        }
+
+        if fract.len() > precision && !truncate {
+            return Err(FixedPointError::TooManyDecimalPlaces {
+                given: fract.len(),
+                max: precision,
+            });
+        }
+
+        let fract = &fract[..fract.len().min(precision)];
+        let whole = whole.bytes().try_fold(0u64, |acc, digit| {
+            acc.checked_mul(10)?.checked_add(u64::from(digit - b'0'))
+        });
+        let fract = fract
+            .bytes()
+            .chain(std::iter::repeat(b'0'))
+            .take(precision)
+            .fold(0, |acc, digit| acc * 10 + u64::from(digit - b'0'));
+
+        whole
+            .and_then(|whole| whole.checked_mul(10u64.pow(precision as u32)))
+            .and_then(|whole| whole.checked_add(fract))
+            .map(FixedPoint)
+            .ok_or(FixedPointError::Overflow)
+    }
+}
+
+impl FromStr for FixedPoint {
+    type Err = FixedPointError;
+
+    /// Parse a decimal string with at most four decimal places, such as `1.5` or `100.0050`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        FixedPoint::parse(s, DECIMALS, false)
+    }
+}
+
+impl fmt::Display for FixedPoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_string_decimal())
+    }
+}
+
+impl From<u64> for FixedPoint {
+    #[inline]
+    fn from(raw: u64) -> Self {
+        FixedPoint(raw)
+    }
+}
+
+impl From<FixedPoint> for u64 {
+    #[inline]
+    fn from(amount: FixedPoint) -> Self {
+        amount.0
+    }
+}
+
+impl PartialEq<u64> for FixedPoint {
+    #[inline]
+    fn eq(&self, other: &u64) -> bool {
+        self.0 == *other
+    }
+}
+
+impl Serialize for FixedPoint {
+    /// Serialize the amount as a decimal string.
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&self.to_string_decimal())
+    }
+}
+
+impl<'de> Deserialize<'de> for FixedPoint {
+    /// Deserialize the amount from a decimal string.
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(d)?;
+        value.parse().map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FixedPoint, FixedPointError};
+
+    #[test]
+    fn from_str() {
+        let cases = [
+            ("0", 0),
+            ("1", 10_000),
+            ("1.5", 15_000),
+            ("1.", 10_000),
+            (".5", 5_000),
+            ("+2.25", 22_500),
+            ("100.0050", 1_000_050),
+            ("0.0001", 1),
+        ];
+
+        for (s, raw) in cases {
+            assert_eq!(
+                s.parse::<FixedPoint>(),
+                Ok(FixedPoint::from_raw(raw)),
+                "{s}"
+            );
+        }
+    }
+
+    #[test]
+    fn from_str_errors() {
+        let cases = [
+            ("", FixedPointError::Empty),
+            (".", FixedPointError::Empty),
+            ("-1", FixedPointError::NegativeAmount),
+            ("-0.5", FixedPointError::NegativeAmount),
+            (
+                "1.23456",
+                FixedPointError::TooManyDecimalPlaces { given: 5, max: 4 },
+            ),
+            (
+                "1.2.3",
+                FixedPointError::InvalidCharacter {
+                    ch: '.',
+                    position: 3,
+                },
+            ),
+            (
+                "+1e3",
+                FixedPointError::InvalidCharacter {
+                    ch: 'e',
+                    position: 2,
+                },
+            ),
+            (
+                "12 5",
+                FixedPointError::InvalidCharacter {
+                    ch: ' ',
+                    position: 2,
+                },
+            ),
+            ("1844674407370956", FixedPointError::Overflow),
+            ("18446744073709551616", FixedPointError::Overflow),
+        ];
+
+        for (s, error) in cases {
+            assert_eq!(s.parse::<FixedPoint>(), Err(error), "{s}");
+        }
+    }
+
+    #[test]
+    fn error_display() {
+        assert_eq!(
+            FixedPointError::TooManyDecimalPlaces { given: 6, max: 4 }.to_string(),
+            "amount has 6 decimal places, at most 4 are supported"
+        );
+        assert_eq!(
+            FixedPointError::InvalidCharacter {
+                ch: 'x',
+                position: 1
+            }
+            .to_string(),
+            "invalid character 'x' at position 1 in amount"
+        );
+    }
+
+    #[test]
+    fn from_str_truncated() {
+        assert_eq!(
+            FixedPoint::from_str_truncated("100.1234567"),
+            Ok(FixedPoint::from_raw(1_001_234))
+        );
+        assert!(FixedPoint::from_str_truncated("1.2345x").is_err());
+    }
+
+    #[test]
+    fn precision() {
+        let amount = FixedPoint::from_str_with_precision("1.239", 2).expect("Failed to parse");
+        assert_eq!(amount, 123);
+        assert_eq!(amount.to_string_with_precision(2), "1.23");
+
+        let amount = FixedPoint::from_str_with_precision("0.5", 8).expect("Failed to parse");
+        assert_eq!(amount, 50_000_000);
+        assert_eq!(amount.to_string_with_precision(8), "0.50000000");
+        assert_eq!(FixedPoint::from_raw(7).to_string_with_precision(0), "7");
+    }
+
+    #[test]
+    fn to_string_decimal() {
+        assert_eq!(FixedPoint::ZERO.to_string_decimal(), "0.0000");
+        assert_eq!(FixedPoint::from_raw(10_050).to_string_decimal(), "1.0050");
+        assert_eq!(FixedPoint::from_raw(1_234_567).to_string(), "123.4567");
+    }
+
+    #[test]
+    fn arithmetic() {
+        let a = FixedPoint::from_raw(15_000);
+        let b = FixedPoint::from_raw(5_000);
+
+        assert_eq!(a.checked_add(b), Some(FixedPoint::from_raw(20_000)));
+        assert_eq!(a.checked_sub(b), Some(FixedPoint::from_raw(10_000)));
+        assert!(b < a);
+        assert_eq!(b.checked_sub(a), None);
+        assert_eq!(FixedPoint::from_raw(u64::MAX).checked_add(b), None);
+        assert_eq!(
+            FixedPoint::from_raw(u64::MAX).saturating_add(b),
+            FixedPoint::from_raw(u64::MAX)
+        );
+    }
+
+    #[test]
+    fn serde_round_trip() {
+        let amount = FixedPoint::from_raw(1_000_050);
+        let json = serde_json::to_string(&amount).expect("Failed to serialize");
+
+        assert_eq!(json, r#""100.0050""#);
+        assert_eq!(
+            serde_json::from_str::<FixedPoint>(&json).expect("Failed to deserialize"),
+            amount
+        );
+    }
+}
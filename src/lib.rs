@@ -0,0 +1,18 @@
+//! Transactor processes account operations from CSV files and reports the resulting account state.
+
+pub mod account;
+pub mod audit;
+#[cfg(feature = "concurrent")]
+pub mod concurrent;
+pub mod config;
+pub mod error;
+pub mod fixed_point;
+pub mod manager;
+pub mod metrics;
+pub mod operation;
+pub mod parse;
+pub mod shared;
+pub mod sorted_manager;
+pub mod stats;
+pub mod transaction_log;
+pub mod undo;
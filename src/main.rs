@@ -1,34 +1,651 @@
-mod account;
-mod error;
-mod manager;
-mod parse;
-
-use manager::Manager;
-use std::env;
+use clap::{Parser, Subcommand};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, IsTerminal, Read, Write};
+use std::path::PathBuf;
 use std::process::ExitCode;
+use transactor::{
+    config::{parse_delimiter, parse_precision, Config, InputFormat, OutputFormat, Verbosity},
+    error::TransactorError,
+    fixed_point::FixedPoint,
+    manager::Manager,
+    parse::{self, ProcessResult},
+    stats::Stats,
+};
 
-#[inline]
-fn execute(file: &str, mut manager: Manager) -> Result<(), error::TransactorError> {
-    parse::load_data(file, &mut manager)?;
-    parse::unload_data(manager)?;
+use tracing_subscriber::{filter::LevelFilter, EnvFilter};
+
+#[cfg(feature = "gzip")]
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+
+/// The subcommand to run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    /// Apply the transactions and write the resulting accounts.
+    Process,
+
+    /// Report every error in the transactions without writing any accounts.
+    Validate,
+
+    /// Apply the transactions and write aggregate statistics instead of the accounts.
+    Stats,
+}
+
+/// The command line interface. Flags are accepted after an optional subcommand.
+#[derive(Debug, Parser)]
+#[command(
+    name = "transactor",
+    version,
+    about = "Process account operations from a CSV file and write the resulting accounts",
+    args_conflicts_with_subcommands = true
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<CliCommand>,
+
+    #[command(flatten)]
+    options: Options,
+}
+
+/// The subcommands, each accepting the same flags as processing.
+#[derive(Debug, Subcommand)]
+enum CliCommand {
+    /// Report every error in the transactions without writing any accounts
+    Validate(Options),
+
+    /// Apply the transactions and write aggregate statistics instead of the accounts
+    Stats(Options),
+}
+
+/// The flags and input shared by every command.
+/// Flags left unset keep the value from the `--config` file, or the default.
+#[derive(Debug, Default, clap::Args)]
+struct Options {
+    /// The input CSV file, or `-` for stdin
+    #[arg(value_name = "INPUT")]
+    input: Option<String>,
+
+    /// Read the input from the path, or stdin if `-`
+    #[arg(long = "input", value_name = "PATH", conflicts_with = "input")]
+    input_path: Option<String>,
+
+    /// Write the output to the path instead of stdout
+    #[arg(long, value_name = "PATH")]
+    output: Option<PathBuf>,
+
+    /// Read settings from a TOML file, overridden by any flags
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    /// Report the outcome of every record
+    #[arg(long, overrides_with = "quiet")]
+    verbose: bool,
+
+    /// Only report fatal errors
+    #[arg(long, overrides_with = "verbose")]
+    quiet: bool,
+
+    /// Halt on the first refused operation
+    #[arg(long, overrides_with = "no_strict")]
+    strict: bool,
+
+    /// Continue past refused operations, overriding the config file
+    #[arg(long, overrides_with = "strict")]
+    no_strict: bool,
+
+    /// Only output the accounts of the client, may be repeated
+    #[arg(short = 'c', long = "client", value_name = "ID")]
+    clients: Vec<u16>,
+
+    /// Write accounts in ascending order of client ID
+    #[arg(long, overrides_with = "no_sort_output")]
+    sort_output: bool,
+
+    /// Write accounts in any order, overriding the config file
+    #[arg(long, overrides_with = "sort_output")]
+    no_sort_output: bool,
+
+    /// The output format, csv or ndjson
+    #[arg(long, value_name = "FORMAT", value_parser = parse_format)]
+    format: Option<OutputFormat>,
+
+    /// The input format, csv or ndjson
+    #[arg(long, value_name = "FORMAT", value_parser = parse_input_format)]
+    input_format: Option<InputFormat>,
+
+    /// The input field delimiter, `\t` for a tab
+    #[arg(long, value_name = "CHAR", value_parser = parse_delimiter)]
+    delimiter: Option<u8>,
+
+    /// The output field delimiter, the input delimiter by default
+    #[arg(long, value_name = "CHAR", value_parser = parse_delimiter)]
+    output_delimiter: Option<u8>,
+
+    /// Read and write CSV without a header row
+    #[arg(long, overrides_with = "header")]
+    no_header: bool,
+
+    /// Read and write CSV with a header row, overriding the config file
+    #[arg(long, overrides_with = "no_header")]
+    header: bool,
+
+    /// Skip the first N lines of the input
+    #[arg(long, value_name = "N")]
+    skip_lines: Option<usize>,
+
+    /// Read and write amounts with 2, 4, 6, or 8 decimal places
+    #[arg(long, value_name = "N", value_parser = parse_precision)]
+    precision: Option<u64>,
+
+    /// Charge fees to frozen accounts
+    #[arg(long, overrides_with = "no_fee_on_frozen")]
+    fee_on_frozen: bool,
+
+    /// Refuse fees on frozen accounts, overriding the config file
+    #[arg(long, overrides_with = "fee_on_frozen")]
+    no_fee_on_frozen: bool,
+
+    /// Skip operations on frozen accounts without reporting them
+    #[arg(long, overrides_with = "no_ignore_frozen")]
+    ignore_frozen: bool,
+
+    /// Report operations on frozen accounts, overriding the config file
+    #[arg(long, overrides_with = "ignore_frozen")]
+    no_ignore_frozen: bool,
+
+    /// Limit the number of deposits tracked per account
+    #[arg(long, value_name = "N", value_parser = parse_max_deposits)]
+    max_deposits: Option<usize>,
+
+    /// Limit the number of operations applied per client
+    #[arg(long, value_name = "N", value_parser = parse_max_ops)]
+    max_ops_per_client: Option<usize>,
+
+    /// Refuse deposits and withdrawals of more than the decimal amount
+    #[arg(long, value_name = "N", value_parser = parse_max_amount)]
+    max_amount: Option<String>,
+
+    /// Write a CSV log of every attempted operation
+    #[arg(long, value_name = "PATH")]
+    audit_log: Option<PathBuf>,
+
+    /// Read and write gzip compressed data, requires the gzip feature
+    #[arg(long, overrides_with = "no_gzip")]
+    gzip: bool,
+
+    /// Read and write uncompressed data, overriding the config file
+    #[arg(long, overrides_with = "gzip")]
+    no_gzip: bool,
+}
+
+/// Switch a setting on or off if either of its pair of flags was passed.
+fn override_flag(setting: &mut bool, on: bool, off: bool) {
+    if on {
+        *setting = true;
+    }
+    if off {
+        *setting = false;
+    }
+}
+
+/// Parse an output format name.
+fn parse_format(arg: &str) -> Result<OutputFormat, String> {
+    match arg {
+        "csv" => Ok(OutputFormat::Csv),
+        "ndjson" => Ok(OutputFormat::Ndjson),
+        _ => Err(format!("Invalid output format {arg}")),
+    }
+}
+
+/// Parse an input format name.
+fn parse_input_format(arg: &str) -> Result<InputFormat, String> {
+    match arg {
+        "csv" => Ok(InputFormat::Csv),
+        "ndjson" => Ok(InputFormat::Ndjson),
+        _ => Err(format!("Invalid input format {arg}")),
+    }
+}
+
+/// Parse a deposit limit, which must be positive.
+fn parse_max_deposits(arg: &str) -> Result<usize, String> {
+    arg.parse()
+        .ok()
+        .filter(|&limit: &usize| limit > 0)
+        .ok_or_else(|| format!("Invalid deposit limit {arg}"))
+}
+
+/// Parse an operation limit, which must be positive.
+fn parse_max_ops(arg: &str) -> Result<usize, String> {
+    arg.parse()
+        .ok()
+        .filter(|&limit: &usize| limit > 0)
+        .ok_or_else(|| format!("Invalid operation limit {arg}"))
+}
+
+/// Check a maximum transaction amount is a valid decimal at the highest supported precision.
+/// The amount is scaled once the configured precision is known.
+fn parse_max_amount(arg: &str) -> Result<String, String> {
+    FixedPoint::from_str_with_precision(arg, 8)
+        .map(|_| arg.to_string())
+        .map_err(|error| format!("Invalid maximum amount {arg}: {error}"))
+}
+
+/// The parsed command line arguments.
+struct Args {
+    command: Command,
+
+    /// The input file path, or `-` for stdin.
+    input: String,
+
+    /// The output file path. Stdout is used if absent.
+    output: Option<PathBuf>,
+
+    config: Config,
+}
+
+/// Open the input file for reading, or stdin if the path is `-`.
+fn open_input(path: &str) -> Result<Box<dyn Read>, TransactorError> {
+    match path {
+        "-" => Ok(Box::new(io::stdin().lock())),
+        path => Ok(Box::new(BufReader::new(File::open(path)?))),
+    }
+}
+
+/// Open the output file for writing, or stdout if no path is provided.
+fn open_output(path: Option<&PathBuf>) -> Result<Box<dyn Write>, TransactorError> {
+    match path {
+        Some(path) => Ok(Box::new(BufWriter::new(File::create(path)?))),
+        None => Ok(Box::new(BufWriter::new(io::stdout().lock()))),
+    }
+}
+
+/// Decompress the input if configured.
+#[cfg(feature = "gzip")]
+fn decompress(reader: Box<dyn Read>, config: &Config) -> Box<dyn Read> {
+    if config.gzip {
+        Box::new(GzDecoder::new(reader))
+    } else {
+        reader
+    }
+}
+
+#[cfg(not(feature = "gzip"))]
+fn decompress(reader: Box<dyn Read>, _config: &Config) -> Box<dyn Read> {
+    reader
+}
+
+/// Serialize the accounts to the writer, compressing the output if configured.
+#[cfg(feature = "gzip")]
+fn write_output<W: Write>(
+    manager: Manager,
+    config: &Config,
+    writer: W,
+) -> Result<(), TransactorError> {
+    if !config.gzip {
+        return parse::dump(manager, config, writer);
+    }
+
+    let mut encoder = GzEncoder::new(writer, Compression::default());
+    parse::dump(manager, config, &mut encoder)?;
+    encoder.finish()?.flush()?;
     Ok(())
 }
 
+#[cfg(not(feature = "gzip"))]
+fn write_output<W: Write>(
+    manager: Manager,
+    config: &Config,
+    writer: W,
+) -> Result<(), TransactorError> {
+    parse::dump(manager, config, writer)
+}
+
+/// Apply the transactions from the reader in the configured input format.
+#[inline]
+fn load_input<R: Read>(
+    reader: R,
+    manager: &mut Manager,
+    config: &Config,
+) -> Result<ProcessResult, TransactorError> {
+    match config.input_format {
+        InputFormat::Csv => parse::load(reader, manager, config),
+        InputFormat::Ndjson => parse::load_ndjson(reader, manager, config),
+    }
+}
+
+#[inline]
+fn execute(args: &Args, mut manager: Manager) -> Result<(), TransactorError> {
+    let config = &args.config;
+    let reader = decompress(open_input(&args.input)?, config);
+    let result = load_input(reader, &mut manager, config)?;
+
+    if config.verbosity == Verbosity::Verbose {
+        eprintln!(
+            "Processed {} records, skipped {}",
+            result.processed, result.skipped
+        );
+    }
+
+    if let Some(path) = &config.audit_log {
+        let writer = BufWriter::new(File::create(path)?);
+        manager.export_audit_csv(writer, config.precision())?;
+    }
+
+    if !config.clients.is_empty() {
+        manager.retain(|client, _| config.clients.contains(&client))?;
+    }
+
+    write_output(manager, config, open_output(args.output.as_ref())?)
+}
+
+/// Report every error in the input to stdout, one per line, without writing any accounts.
+/// Returns whether the input is free of errors.
+#[inline]
+fn validate(args: &Args) -> Result<bool, TransactorError> {
+    let config = &args.config;
+    let reader = decompress(open_input(&args.input)?, config);
+    let errors = parse::validate_data(reader, config)?;
+
+    let mut writer = BufWriter::new(io::stdout().lock());
+    for (row, error) in &errors {
+        writeln!(writer, "row {row}: {}: {error}", error.kind())?;
+    }
+    writer.flush()?;
+
+    Ok(errors.is_empty())
+}
+
+/// Apply the transactions and write aggregate statistics over every account.
+#[inline]
+fn stats(args: &Args, mut manager: Manager) -> Result<(), TransactorError> {
+    let config = &args.config;
+    let reader = decompress(open_input(&args.input)?, config);
+    let result = load_input(reader, &mut manager, config)?;
+
+    if !config.clients.is_empty() {
+        manager.retain(|client, _| config.clients.contains(&client))?;
+    }
+
+    let stats = Stats::new(&manager, &result);
+    parse::write_stats(
+        &stats,
+        open_output(args.output.as_ref())?,
+        config.output_format,
+        config.precision(),
+    )
+}
+
+impl Cli {
+    /// Resolve the parsed flags into the command to run and its arguments.
+    /// Settings from a `--config` file are applied first, so that flags take precedence.
+    fn into_args(self) -> Result<Args, String> {
+        let (command, options) = match self.command {
+            None => (Command::Process, self.options),
+            Some(CliCommand::Validate(options)) => (Command::Validate, options),
+            Some(CliCommand::Stats(options)) => (Command::Stats, options),
+        };
+
+        let mut config = match &options.config {
+            Some(path) => Config::load(path).map_err(|error| error.to_string())?,
+            None => Config::default(),
+        };
+
+        if options.verbose {
+            config.verbosity = Verbosity::Verbose;
+        }
+        if options.quiet {
+            config.verbosity = Verbosity::Quiet;
+        }
+        if !options.clients.is_empty() {
+            config.clients = options.clients;
+        }
+        if let Some(format) = options.format {
+            config.output_format = format;
+        }
+        if let Some(format) = options.input_format {
+            config.input_format = format;
+        }
+        if let Some(delimiter) = options.delimiter {
+            config.delimiter = delimiter;
+        }
+        if let Some(delimiter) = options.output_delimiter {
+            config.output_delimiter = Some(delimiter);
+        }
+        if let Some(lines) = options.skip_lines {
+            config.skip_lines = lines;
+        }
+        if let Some(scale) = options.precision {
+            config.decimal_scale = scale;
+        }
+        if let Some(limit) = options.max_deposits {
+            config.max_deposits_per_account = Some(limit);
+        }
+        if let Some(limit) = options.max_ops_per_client {
+            config.max_ops_per_client = Some(limit);
+        }
+        if let Some(amount) = options.max_amount {
+            let amount = FixedPoint::from_str_with_precision(&amount, config.precision())
+                .map_err(|error| format!("Invalid maximum amount {amount}: {error}"))?;
+            config.max_transaction_amount = Some(amount.raw());
+        }
+        if let Some(path) = options.audit_log {
+            config.audit_log = Some(path);
+        }
+
+        override_flag(&mut config.strict, options.strict, options.no_strict);
+        override_flag(
+            &mut config.sort_output,
+            options.sort_output,
+            options.no_sort_output,
+        );
+        override_flag(&mut config.no_header, options.no_header, options.header);
+        override_flag(
+            &mut config.fee_on_frozen,
+            options.fee_on_frozen,
+            options.no_fee_on_frozen,
+        );
+        override_flag(
+            &mut config.ignore_frozen,
+            options.ignore_frozen,
+            options.no_ignore_frozen,
+        );
+        override_flag(&mut config.gzip, options.gzip, options.no_gzip);
+
+        if config.gzip && !cfg!(feature = "gzip") {
+            return Err("gzip requires building with the gzip feature".to_string());
+        }
+        if command == Command::Validate && config.input_format != InputFormat::Csv {
+            return Err("validate only supports csv input".to_string());
+        }
+
+        let input = options
+            .input
+            .or(options.input_path)
+            .ok_or("Missing csv file parameter")?;
+        Ok(Args {
+            command,
+            input,
+            output: options.output,
+            config,
+        })
+    }
+}
+
+/// Install a subscriber writing tracing events to stderr, filtered by `RUST_LOG`.
+/// No events are written when `RUST_LOG` is unset.
+fn init_tracing() {
+    let filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::OFF.into())
+        .from_env_lossy();
+
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(io::stderr)
+        .with_ansi(io::stderr().is_terminal())
+        .init();
+}
+
 fn main() -> ExitCode {
-    let file = match env::args().nth(1) {
-        Some(file) => file,
-        None => {
-            eprintln!("Error: Missing csv file parameter");
+    init_tracing();
+
+    let args = match Cli::parse().into_args() {
+        Ok(args) => args,
+        Err(error) => {
+            eprintln!("Error: {error}");
             return ExitCode::FAILURE;
         }
     };
 
-    let manager = Manager::new();
+    let result = match args.command {
+        Command::Process => execute(&args, Manager::from_config(&args.config)).map(|()| true),
+        Command::Validate => validate(&args),
+        Command::Stats => stats(&args, Manager::from_config(&args.config)).map(|()| true),
+    };
+
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(error) => {
+            tracing::error!(%error, "fatal error");
+            eprintln!("Fatal Error: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Cli, CliCommand, Command};
+    use clap::Parser;
+    use transactor::config::{InputFormat, OutputFormat, Verbosity};
+
+    #[test]
+    fn parse_flags() {
+        let cli =
+            Cli::try_parse_from(["transactor", "--strict", "input.csv"]).expect("Failed to parse");
+
+        assert!(cli.command.is_none());
+        assert!(cli.options.strict);
+        assert_eq!(cli.options.input.as_deref(), Some("input.csv"));
+        assert!(!cli.options.sort_output);
 
-    if let Err(error) = execute(&file, manager) {
-        eprintln!("Fatal Error: {error}");
-        return ExitCode::FAILURE;
+        let args = cli.into_args().expect("Invalid arguments");
+        assert_eq!(args.command, Command::Process);
+        assert_eq!(args.input, "input.csv");
+        assert!(args.config.strict);
     }
 
-    ExitCode::SUCCESS
+    #[test]
+    fn parse_negated_flags() {
+        let args = Cli::try_parse_from([
+            "transactor",
+            "--strict",
+            "--no-strict",
+            "--no-sort-output",
+            "--gzip",
+            "--no-gzip",
+            "--no-header",
+            "--header",
+            "input.csv",
+        ])
+        .expect("Failed to parse")
+        .into_args()
+        .expect("Invalid arguments");
+
+        assert!(!args.config.strict);
+        assert!(!args.config.sort_output);
+        assert!(!args.config.gzip);
+        assert!(!args.config.no_header);
+
+        // The last of a pair of flags wins
+        let args = Cli::try_parse_from(["transactor", "--no-strict", "--strict", "input.csv"])
+            .expect("Failed to parse")
+            .into_args()
+            .expect("Invalid arguments");
+        assert!(args.config.strict);
+    }
+
+    #[test]
+    fn parse_subcommand() {
+        let cli = Cli::try_parse_from([
+            "transactor",
+            "stats",
+            "--format",
+            "ndjson",
+            "-c",
+            "1",
+            "--client",
+            "2",
+            "--quiet",
+            "--input",
+            "-",
+        ])
+        .expect("Failed to parse");
+        assert!(matches!(cli.command, Some(CliCommand::Stats(_))));
+
+        let args = cli.into_args().expect("Invalid arguments");
+        assert_eq!(args.command, Command::Stats);
+        assert_eq!(args.input, "-");
+        assert_eq!(args.config.output_format, OutputFormat::Ndjson);
+        assert_eq!(args.config.clients, [1, 2]);
+        assert_eq!(args.config.verbosity, Verbosity::Quiet);
+    }
+
+    #[test]
+    fn parse_max_amount() {
+        let args = Cli::try_parse_from(["transactor", "--max-amount", "9.9999", "input.csv"])
+            .expect("Failed to parse")
+            .into_args()
+            .expect("Invalid arguments");
+        assert_eq!(args.config.max_transaction_amount, Some(99_999));
+
+        // The amount is scaled to the configured precision, wherever the flag appears
+        let args = Cli::try_parse_from([
+            "transactor",
+            "--max-amount",
+            "9.9999",
+            "--precision",
+            "2",
+            "input.csv",
+        ])
+        .expect("Failed to parse")
+        .into_args()
+        .expect("Invalid arguments");
+        assert_eq!(args.config.max_transaction_amount, Some(999));
+    }
+
+    #[test]
+    fn parse_input_format() {
+        let args = Cli::try_parse_from(["transactor", "--input-format", "ndjson", "input.json"])
+            .expect("Failed to parse")
+            .into_args()
+            .expect("Invalid arguments");
+        assert_eq!(args.config.input_format, InputFormat::Ndjson);
+
+        assert!(Cli::try_parse_from([
+            "transactor",
+            "validate",
+            "--input-format",
+            "ndjson",
+            "input.json"
+        ])
+        .expect("Failed to parse")
+        .into_args()
+        .is_err());
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert!(Cli::try_parse_from(["transactor", "--format", "xml", "input.csv"]).is_err());
+        assert!(Cli::try_parse_from(["transactor", "--input-format", "xml", "input.csv"]).is_err());
+        assert!(Cli::try_parse_from(["transactor", "a.csv", "b.csv"]).is_err());
+        assert!(Cli::try_parse_from(["transactor", "--input", "a.csv", "b.csv"]).is_err());
+        assert!(Cli::try_parse_from(["transactor", "--max-amount", "-1", "input.csv"]).is_err());
+        assert!(
+            Cli::try_parse_from(["transactor", "--max-ops-per-client", "0", "input.csv"]).is_err()
+        );
+        assert!(Cli::try_parse_from(["transactor"])
+            .expect("Failed to parse")
+            .into_args()
+            .is_err());
+    }
 }
@@ -1,160 +1,2191 @@
-use crate::{account::Account, error::TransactorError};
-use std::collections::{
-    hash_map::{IntoIter, Iter},
-    HashMap,
+use crate::{
+    account::Account,
+    audit::AuditEntry,
+    config::Config,
+    error::TransactorError,
+    metrics::Metrics,
+    operation::Operation,
+    parse::{self, read_snapshot, write_audit_log, write_snapshot, ProcessResult},
+    transaction_log::{LogEntry, TransactionLog},
+    undo::UndoStack,
 };
+use indexmap::{
+    map::{Entry, IntoIter, Iter},
+    IndexMap,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    io::{Read, Write},
+};
+use tracing::instrument;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// The handling of a client ID present in both managers during a merge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeStrategy {
+    /// Keep the existing account, discarding the other.
+    SkipConflicts,
+
+    /// Replace the existing account with the other.
+    OverwriteWithOther,
+
+    /// Refuse the merge, leaving the `Manager` unchanged.
+    ErrorOnConflict,
+}
+
+/// The change to a single account between two states of a `Manager`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccountDiff {
+    pub client: u16,
+    pub available_delta: i64,
+    pub held_delta: i64,
+    pub newly_frozen: bool,
+}
+
+/// A single operation to apply to a `Manager`, independent of the source it was read from.
+/// Transfers require a target client, which is ignored by every other operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RawRecord {
+    pub operation: Operation,
+    pub client: u16,
+    pub tx: u32,
+    pub amount: Option<u64>,
+    pub target: Option<u16>,
+}
+
+impl RawRecord {
+    /// Construct a new `RawRecord` without a target client.
+    #[inline]
+    pub fn new(operation: Operation, client: u16, tx: u32, amount: Option<u64>) -> Self {
+        RawRecord {
+            operation,
+            client,
+            tx,
+            amount,
+            target: None,
+        }
+    }
+}
+
+/// The funds at risk of being charged back across every account of a `Manager`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RiskReport {
+    /// The sum of every disputed deposit, saturating rather than overflowing.
+    pub total_at_risk: u64,
+
+    /// The number of accounts with at least one disputed deposit.
+    pub accounts_with_disputes: usize,
+
+    /// The amount of the largest single disputed deposit.
+    pub highest_single_dispute: u64,
+
+    /// The client with the largest sum of disputed deposits, the first created on a tie.
+    pub client_with_highest_exposure: Option<u16>,
+}
+
+/// Get the signed change from one amount to another, saturating at the bounds of an i64.
+#[inline]
+fn signed_delta(before: u64, after: u64) -> i64 {
+    let delta = i128::from(after) - i128::from(before);
+    i64::try_from(delta).unwrap_or(if delta < 0 { i64::MIN } else { i64::MAX })
+}
+
+/// Refuse an amount exceeding the maximum amount of a single transaction, if any.
+#[inline]
+pub(crate) fn check_amount(amt: u64, max: Option<u64>) -> Result<(), TransactorError> {
+    match max {
+        Some(max) if amt > max => Err(TransactorError::AmountTooLarge { amount: amt, max }),
+        _ => Ok(()),
+    }
+}
+
+/// Deposit funds into the account, refusing the deposit if the account has reached the limit of tracked deposits,
+/// if the amount exceeds the maximum amount, or if the transaction ID is the largest,
+/// exhausting the transaction ID space.
+#[inline]
+pub(crate) fn deposit_limited(
+    acct: &mut Account,
+    client: u16,
+    tx: u32,
+    amt: u64,
+    description: Option<String>,
+    limit: Option<usize>,
+    max_amount: Option<u64>,
+) -> Result<(), TransactorError> {
+    if tx == u32::MAX {
+        return Err(TransactorError::TxIdSpaceExhausted);
+    }
+    check_amount(amt, max_amount)?;
+
+    match limit {
+        Some(limit) if acct.deposit_count() >= limit => {
+            Err(TransactorError::DepositLimitExceeded { client, limit })
+        }
+        _ => acct.deposit(tx, amt, description),
+    }
+}
+
+/// Open an account for a new client with its first deposit, subject to the same limits as `deposit_limited`.
+/// A zero amount is refused, rather than creating an account with no funds.
+#[inline]
+pub(crate) fn open_account(
+    client: u16,
+    tx: u32,
+    amt: u64,
+    description: Option<String>,
+    limit: Option<usize>,
+    max_amount: Option<u64>,
+) -> Result<Account, TransactorError> {
+    if amt == 0 {
+        return Err(TransactorError::ZeroAmount);
+    }
+
+    let mut acct = Account::new_empty();
+    deposit_limited(&mut acct, client, tx, amt, description, limit, max_amount)?;
+    Ok(acct)
+}
+
+/// A saved copy of the accounts of a `Manager`, restored by name.
+#[derive(Debug, Clone, Default)]
+pub struct ManagerState(IndexMap<u16, Account>);
+
+/// Account manager associating a client ID to an account.
+/// Accounts are iterated in the order they were created.
+/// Clones share the transaction log of the original, if recording.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manager {
+    accounts: IndexMap<u16, Account>,
+    #[serde(default)]
+    max_seen_tx: u32,
+    #[serde(skip)]
+    audit_log: Option<Vec<AuditEntry>>,
+    #[serde(skip)]
+    max_deposits: Option<usize>,
+    #[serde(skip)]
+    max_amount: Option<u64>,
+    #[serde(skip)]
+    max_ops: Option<usize>,
+    #[serde(skip)]
+    client_ops: HashMap<u16, usize>,
+    #[serde(skip)]
+    metrics: Option<Metrics>,
+    #[serde(skip)]
+    undo: UndoStack,
+    #[serde(skip)]
+    checkpoints: HashMap<String, ManagerState>,
+    #[serde(skip)]
+    log: Option<TransactionLog>,
+}
+
+impl Default for Manager {
+    #[inline]
+    fn default() -> Self {
+        Manager::new()
+    }
+}
+
+impl Manager {
+    /// Construct a new `Manager`.
+    #[inline]
+    pub fn new() -> Self {
+        Manager {
+            accounts: IndexMap::new(),
+            max_seen_tx: 0,
+            audit_log: None,
+            max_deposits: None,
+            max_amount: None,
+            max_ops: None,
+            client_ops: HashMap::new(),
+            metrics: None,
+            undo: UndoStack::default(),
+            checkpoints: HashMap::new(),
+            log: None,
+        }
+    }
+
+    /// Construct a new `Manager` that collects operation metrics.
+    #[inline]
+    pub fn with_metrics() -> Self {
+        Manager {
+            metrics: Some(Metrics::default()),
+            ..Manager::new()
+        }
+    }
+
+    /// Construct a new `Manager` that records every applied operation,
+    /// along with the `TransactionLog` it records them in.
+    #[inline]
+    pub fn with_log() -> (Self, TransactionLog) {
+        let log = TransactionLog::default();
+        let manager = Manager {
+            log: Some(log.clone()),
+            ..Manager::new()
+        };
+        (manager, log)
+    }
+
+    /// Construct a new `Manager` according to the `Config`.
+    #[inline]
+    pub fn from_config(config: &Config) -> Self {
+        Manager {
+            accounts: IndexMap::new(),
+            max_seen_tx: 0,
+            audit_log: config.audit_log.as_ref().map(|_| Vec::new()),
+            max_deposits: config.max_deposits_per_account,
+            max_amount: config.max_transaction_amount,
+            max_ops: config.max_ops_per_client,
+            client_ops: HashMap::new(),
+            metrics: None,
+            undo: UndoStack::new(config.undo_depth),
+            checkpoints: HashMap::new(),
+            log: None,
+        }
+    }
+
+    /// Construct a new `Manager` according to the `Config` and apply every transaction record read as CSV from the reader,
+    /// as `parse::load` does.
+    #[inline]
+    pub fn from_csv<R: Read>(
+        reader: R,
+        config: &Config,
+    ) -> Result<(Manager, ProcessResult), TransactorError> {
+        let mut manager = Manager::from_config(config);
+        let result = parse::load(reader, &mut manager, config)?;
+        Ok((manager, result))
+    }
+
+    /// Count an operation of the client against the configured maximum number of operations per client.
+    /// Once the client exceeds the limit, this and every later operation of the client is refused.
+    /// Always succeeds when unlimited.
+    #[inline]
+    pub fn count_client_op(&mut self, client: u16) -> Result<(), TransactorError> {
+        let Some(limit) = self.max_ops else {
+            return Ok(());
+        };
+
+        let count = self.client_ops.entry(client).or_default();
+        *count += 1;
+
+        if *count > limit {
+            return Err(TransactorError::ClientRateLimited(client));
+        }
+        Ok(())
+    }
+
+    /// Return whether attempted operations are recorded in the audit log.
+    #[inline]
+    pub fn is_auditing(&self) -> bool {
+        self.audit_log.is_some()
+    }
+
+    /// Record an attempted operation in the audit log, if enabled.
+    #[inline]
+    pub fn record_audit(&mut self, entry: AuditEntry) {
+        if let Some(log) = &mut self.audit_log {
+            log.push(entry);
+        }
+    }
+
+    /// Get the audit log of every operation attempted, in the order they were attempted.
+    /// Empty if the audit log is not enabled.
+    #[inline]
+    pub fn audit_trail(&self) -> &[AuditEntry] {
+        self.audit_log.as_deref().unwrap_or_default()
+    }
+
+    /// Iterate the client ID of every account, in the order they were created. O(n) to exhaust.
+    #[inline]
+    pub fn client_ids(&self) -> impl Iterator<Item = u16> + '_ {
+        self.accounts.keys().copied()
+    }
+
+    /// Iterate every account paired with its client ID, in the order they were created.
+    #[inline]
+    pub fn accounts_iter(&self) -> impl Iterator<Item = (u16, &Account)> + '_ {
+        self.accounts.iter().map(|(&client, acct)| (client, acct))
+    }
+
+    /// Iterate every account mutably paired with its client ID, in the order they were created.
+    #[inline]
+    pub fn accounts_iter_mut(&mut self) -> impl Iterator<Item = (u16, &mut Account)> + '_ {
+        self.accounts
+            .iter_mut()
+            .map(|(&client, acct)| (client, acct))
+    }
+
+    /// Iterate the accounts with funds held under dispute, in the order they were created.
+    /// Every account is scanned, so this is O(n) to exhaust.
+    #[inline]
+    pub fn clients_with_disputes(&self) -> impl Iterator<Item = (&u16, &Account)> + '_ {
+        self.accounts.iter().filter(|(_, acct)| acct.held() > 0)
+    }
+
+    /// Get the client ID, transaction ID, and held amount of every currently disputed deposit,
+    /// sorted by client ID and then transaction ID, for reconciliation with an external chargeback processor.
+    #[inline]
+    pub fn pending_disputes_report(&self) -> Vec<(u16, u32, u64)> {
+        let mut report: Vec<(u16, u32, u64)> = self
+            .accounts
+            .iter()
+            .flat_map(|(&client, acct)| {
+                acct.deposit_iter()
+                    .filter(|(_, deposit)| deposit.is_disputed())
+                    .map(move |(tx, deposit)| (client, tx, deposit.amount()))
+            })
+            .collect();
+
+        report.sort_unstable();
+        report
+    }
+
+    /// Get the transaction ID following the largest used by any applied deposit, withdrawal, transfer, or fee,
+    /// or `None` if the largest transaction ID is in use. Scanning the whole transaction ID space
+    /// for the smallest unused ID is not feasible, so gaps below the largest ID are not reused.
+    /// The largest ID is kept even after its transaction is no longer tracked, such as when its account is removed,
+    /// so an ID is never handed out twice. O(1).
+    #[inline]
+    pub fn next_available_tx_id(&self) -> Option<u32> {
+        self.max_seen_tx.checked_add(1)
+    }
+
+    /// Get the number of accounts. O(1).
+    #[inline]
+    pub fn account_count(&self) -> usize {
+        self.accounts.len()
+    }
+
+    /// Return whether there are no accounts. O(1).
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+    }
+
+    /// Get the operation metrics, if collected.
+    #[inline]
+    pub fn metrics(&self) -> Option<&Metrics> {
+        self.metrics.as_ref()
+    }
+
+    /// Count the outcome of the operation in the metrics, if collected, and pass it through.
+    #[inline]
+    fn track(
+        &mut self,
+        operation: Operation,
+        result: Result<(), TransactorError>,
+    ) -> Result<(), TransactorError> {
+        if let Some(metrics) = &mut self.metrics {
+            metrics.record(operation, result.is_ok());
+        }
+
+        result
+    }
+
+    /// Raise the largest seen transaction ID to the ID of the operation, if it succeeded.
+    #[inline]
+    fn see_tx(&mut self, result: &Result<(), TransactorError>, tx: u32) {
+        if result.is_ok() {
+            self.max_seen_tx = self.max_seen_tx.max(tx);
+        }
+    }
+
+    /// Refuse an operation that replaces accounts wholesale, rather than applying a transaction,
+    /// if recording a transaction log, as the log could no longer be replayed to the same state.
+    #[inline]
+    fn ensure_replayable(&self, name: &str) -> Result<(), TransactorError> {
+        match self.log {
+            Some(_) => Err(TransactorError::NotReplayable(name.to_string())),
+            None => Ok(()),
+        }
+    }
+
+    /// Record the operation in the transaction log, if recording and the operation was applied.
+    #[inline]
+    fn log_applied(&self, result: &Result<(), TransactorError>, entry: LogEntry) {
+        if let (Some(log), Ok(())) = (&self.log, result) {
+            log.push(entry);
+        }
+    }
+
+    /// Apply the operation, recording the prior state of the affected accounts if it succeeds.
+    /// The state is only captured when undo is enabled.
+    #[inline]
+    fn undoable<F>(&mut self, clients: &[u16], f: F) -> Result<(), TransactorError>
+    where
+        F: FnOnce(&mut Self) -> Result<(), TransactorError>,
+    {
+        if !self.undo.is_enabled() {
+            return f(self);
+        }
+
+        let snapshot = clients
+            .iter()
+            .map(|&client| (client, self.accounts.get(&client).cloned()))
+            .collect();
+        f(self)?;
+        self.undo.push(snapshot);
+        Ok(())
+    }
+
+    /// Roll back the most recent operation, restoring the affected accounts to their prior state.
+    /// Accounts created by the operation are removed. Refused when recording a transaction log.
+    #[inline]
+    pub fn undo(&mut self) -> Result<(), TransactorError> {
+        self.ensure_replayable("undo")?;
+        let snapshot = self.undo.pop().ok_or(TransactorError::NothingToUndo)?;
+
+        for (client, acct) in snapshot.into_iter().rev() {
+            match acct {
+                Some(acct) => {
+                    self.accounts.insert(client, acct);
+                }
+                None => {
+                    self.accounts.shift_remove(&client);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Save a copy of every account under the name, replacing any checkpoint of the same name.
+    #[inline]
+    pub fn checkpoint(&mut self, name: &str) -> Result<(), TransactorError> {
+        self.checkpoints
+            .insert(name.to_string(), ManagerState(self.accounts.clone()));
+        Ok(())
+    }
+
+    /// Replace every account with the copy saved under the name. The checkpoint is kept,
+    /// so it may be restored again, while operations recorded for undo are discarded.
+    /// Refused when recording a transaction log.
+    #[inline]
+    pub fn restore_checkpoint(&mut self, name: &str) -> Result<(), TransactorError> {
+        self.ensure_replayable("restore_checkpoint")?;
+        let ManagerState(accounts) = self
+            .checkpoints
+            .get(name)
+            .ok_or_else(|| TransactorError::NoCheckpoint(name.to_string()))?;
+
+        self.accounts = accounts.clone();
+        self.undo.clear();
+        Ok(())
+    }
+
+    /// Discard the checkpoint saved under the name, if any, freeing its memory.
+    #[inline]
+    pub fn drop_checkpoint(&mut self, name: &str) {
+        self.checkpoints.remove(name);
+    }
+
+    /// Compare two states of the accounts, returning the change to each account that differs.
+    /// Accounts present in only one of the managers are compared against an empty account,
+    /// so new accounts show their full balance as a positive change. Unchanged accounts are omitted.
+    #[inline]
+    pub fn diff(before: &Manager, after: &Manager) -> Vec<AccountDiff> {
+        let empty = Account::new_empty();
+        let removed = before
+            .accounts
+            .keys()
+            .filter(|client| !after.accounts.contains_key(*client));
+
+        after
+            .accounts
+            .keys()
+            .chain(removed)
+            .filter_map(|&client| {
+                let old = before.accounts.get(&client).unwrap_or(&empty);
+                let new = after.accounts.get(&client).unwrap_or(&empty);
+                let diff = AccountDiff {
+                    client,
+                    available_delta: signed_delta(old.available(), new.available()),
+                    held_delta: signed_delta(old.held(), new.held()),
+                    newly_frozen: new.is_frozen() && !old.is_frozen(),
+                };
+
+                let changed =
+                    diff.available_delta != 0 || diff.held_delta != 0 || diff.newly_frozen;
+                changed.then_some(diff)
+            })
+            .collect()
+    }
+
+    /// Get the sum of the disputed deposits across every account.
+    /// Returns `None` if the sum overflows.
+    #[inline]
+    pub fn portfolio_at_risk(&self) -> Option<u64> {
+        self.accounts.values().try_fold(0u64, |sum, acct| {
+            sum.checked_add(acct.deposit_value_at_risk()?)
+        })
+    }
+
+    /// Summarize the disputed deposits across every account in a single pass.
+    #[inline]
+    pub fn risk_report(&self) -> RiskReport {
+        let mut report = RiskReport::default();
+        let mut highest_exposure = 0;
+
+        for (&client, acct) in &self.accounts {
+            let mut disputed = acct
+                .deposit_iter()
+                .filter(|(_, deposit)| deposit.is_disputed())
+                .peekable();
+
+            if disputed.peek().is_none() {
+                continue;
+            }
+
+            let mut exposure = 0u64;
+            for (_, deposit) in disputed {
+                exposure = exposure.saturating_add(deposit.amount());
+                report.highest_single_dispute = report.highest_single_dispute.max(deposit.amount());
+            }
+
+            report.accounts_with_disputes += 1;
+            report.total_at_risk = report.total_at_risk.saturating_add(exposure);
+
+            if report.client_with_highest_exposure.is_none() || exposure > highest_exposure {
+                report.client_with_highest_exposure = Some(client);
+                highest_exposure = exposure;
+            }
+        }
+
+        report
+    }
+
+    /// Get the account specified by the client ID.
+    #[inline]
+    fn account_mut(&mut self, client: u16) -> Result<&mut Account, TransactorError> {
+        self.accounts
+            .get_mut(&client)
+            .ok_or(TransactorError::NoClient(client))
+    }
+
+    /// Serialize the audit log as CSV and write it to the writer,
+    /// with amounts written to the number of decimal places.
+    #[inline]
+    pub fn export_audit_csv<W: Write>(
+        &self,
+        writer: W,
+        precision: usize,
+    ) -> Result<(), TransactorError> {
+        write_audit_log(self.audit_trail(), writer, precision)
+    }
+
+    /// Serialize the deposit history of the account specified by the client ID as CSV and write it to the writer.
+    #[inline]
+    pub fn export_client_history<W: Write>(
+        &self,
+        client: u16,
+        writer: W,
+    ) -> Result<(), TransactorError> {
+        self.accounts
+            .get(&client)
+            .ok_or(TransactorError::NoClient(client))?
+            .export_history(writer)
+    }
+
+    /// Write every account record to the writer as `parse::dump_ref` does,
+    /// in the configured output format, CSV by default.
+    #[inline]
+    pub fn to_csv<W: Write>(&self, writer: W, config: &Config) -> Result<(), TransactorError> {
+        parse::dump_ref(self, config, writer)
+    }
+
+    /// Serialize the state of every account, including deposit histories, and write it to the writer.
+    /// The audit log is not included in the snapshot.
+    #[inline]
+    pub fn save<W: Write>(&self, writer: W) -> Result<(), TransactorError> {
+        write_snapshot(self, writer)
+    }
+
+    /// Restore a `Manager` from a snapshot previously written by `save`.
+    #[inline]
+    pub fn load<R: Read>(reader: R) -> Result<Manager, TransactorError> {
+        read_snapshot(reader)
+    }
+
+    /// Merge the accounts of another `Manager` into this one.
+    /// Conflicting client IDs are handled according to the `MergeStrategy`.
+    /// Refused when recording a transaction log.
+    #[inline]
+    pub fn merge(
+        &mut self,
+        other: Manager,
+        strategy: MergeStrategy,
+    ) -> Result<(), TransactorError> {
+        self.ensure_replayable("merge")?;
+
+        if strategy == MergeStrategy::ErrorOnConflict {
+            let conflict = other
+                .accounts
+                .keys()
+                .find(|&client| self.accounts.contains_key(client));
+
+            if let Some(&client) = conflict {
+                return Err(TransactorError::ClientAlreadyExists(client));
+            }
+        }
+
+        self.max_seen_tx = self.max_seen_tx.max(other.max_seen_tx);
+        for (client, acct) in other.accounts {
+            if strategy == MergeStrategy::OverwriteWithOther {
+                self.accounts.insert(client, acct);
+            } else {
+                self.accounts.entry(client).or_insert(acct);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Consume the `Manager`, returning every account sorted in ascending order of client ID.
+    #[inline]
+    pub fn drain_into_sorted_vec(self) -> Vec<(u16, Account)> {
+        let mut accounts: Vec<_> = self.accounts.into_iter().collect();
+        accounts.sort_unstable_by_key(|&(client, _)| client);
+        accounts
+    }
+
+    /// Get every account, sorted according to the comparison function.
+    #[inline]
+    pub fn into_sorted_vec_by<F>(&self, f: F) -> Vec<(u16, &Account)>
+    where
+        F: Fn(&(u16, &Account), &(u16, &Account)) -> std::cmp::Ordering,
+    {
+        let mut accounts: Vec<_> = self.accounts_iter().collect();
+        accounts.sort_by(f);
+        accounts
+    }
+
+    /// Retain only the accounts for which the predicate returns true.
+    /// Refused when recording a transaction log.
+    #[inline]
+    pub fn retain<F: FnMut(u16, &Account) -> bool>(
+        &mut self,
+        mut f: F,
+    ) -> Result<(), TransactorError> {
+        self.ensure_replayable("retain")?;
+        self.accounts.retain(|&client, acct| f(client, acct));
+        Ok(())
+    }
+
+    /// Check the invariants of every account, collecting each violation paired with its client ID.
+    #[inline]
+    pub fn is_consistent(&self) -> Result<(), Vec<(u16, String)>> {
+        let failures: Vec<(u16, String)> = self
+            .accounts
+            .iter()
+            .filter_map(|(&client, acct)| acct.validate_invariants().err().map(|err| (client, err)))
+            .collect();
+
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(failures)
+        }
+    }
+
+    /// Remove every account with no funds that is not frozen, returning the number removed.
+    /// Frozen accounts are kept regardless of their balance. The deposit history of a removed
+    /// account is discarded with it, so its transactions can no longer be disputed.
+    /// Refused when recording a transaction log.
+    #[inline]
+    pub fn remove_empty_accounts(&mut self) -> Result<usize, TransactorError> {
+        self.ensure_replayable("remove_empty_accounts")?;
+        let count = self.accounts.len();
+        self.accounts
+            .retain(|_, acct| acct.total() > 0 || acct.is_frozen());
+        Ok(count - self.accounts.len())
+    }
+
+    /// Remove the account specified by the client ID, if it can be closed.
+    /// An account that holds funds, has disputed deposits, or is frozen is kept, with the reason it was refused.
+    /// Refused when recording a transaction log.
+    #[inline]
+    pub fn close_account(&mut self, client: u16) -> Result<(), TransactorError> {
+        self.ensure_replayable("close_account")?;
+        let acct = self
+            .accounts
+            .get(&client)
+            .ok_or(TransactorError::NoClient(client))?;
+
+        if !acct.can_be_closed() {
+            let reason = if acct.is_frozen() {
+                "account is frozen".to_string()
+            } else if acct.held_deposit_ids().next().is_some() {
+                "account has disputed deposits".to_string()
+            } else {
+                format!("account holds funds of {}", acct.total_as_decimal())
+            };
+            return Err(TransactorError::AccountNotCloseable { reason });
+        }
+
+        self.accounts.shift_remove(&client);
+        Ok(())
+    }
+
+    /// Apply a single operation to the account specified by the client ID,
+    /// decoupling the source of the record from the account logic.
+    /// Deposits, withdrawals, and fees require an amount. Fees are not charged to frozen accounts.
+    /// Transfers require a target client and must be applied with `transfer`.
+    /// Registration and administrative unfreezes ignore the transaction ID.
+    #[inline]
+    pub fn apply_record(
+        &mut self,
+        op: Operation,
+        client: u16,
+        tx: u32,
+        amount: Option<u64>,
+    ) -> Result<(), TransactorError> {
+        match op {
+            Operation::Withdrawal => {
+                let amt = amount.ok_or(TransactorError::MissingAmount)?;
+                self.withdraw(client, tx, amt)
+            }
+            Operation::Deposit => {
+                let amt = amount.ok_or(TransactorError::MissingAmount)?;
+                self.deposit(client, tx, amt, None)
+            }
+            Operation::Dispute => self.dispute(client, tx),
+            Operation::Resolve => self.resolve(client, tx),
+            Operation::Chargeback => self.chargeback(client, tx),
+            Operation::Transfer => Err(TransactorError::MissingTarget),
+            Operation::Fee => {
+                let amt = amount.ok_or(TransactorError::MissingAmount)?;
+                self.apply_fee(client, tx, amt, false)
+            }
+            Operation::Register => self.register_client(client),
+            Operation::AdminUnfreeze => self.set_client_frozen(client, false),
+            Operation::AdminFreeze => self.set_client_frozen(client, true),
+        }
+    }
+
+    /// Apply the record, including transfers to the target client.
+    #[inline]
+    fn apply_raw(&mut self, record: &RawRecord) -> Result<(), TransactorError> {
+        match record.operation {
+            Operation::Transfer => {
+                let amt = record.amount.ok_or(TransactorError::MissingAmount)?;
+                let target = record.target.ok_or(TransactorError::MissingTarget)?;
+                self.transfer(record.client, target, record.tx, amt)
+            }
+            op => self.apply_record(op, record.client, record.tx, record.amount),
+        }
+    }
+
+    /// Apply every record or none of them. The records are applied in order to a copy of the `Manager`,
+    /// which replaces it only if all of them succeed. Otherwise, the `Manager` is left unchanged
+    /// and the index of the first refused record is returned with its error.
+    #[inline]
+    pub fn apply_batch_atomic(
+        &mut self,
+        records: &[RawRecord],
+    ) -> Result<Vec<()>, (usize, TransactorError)> {
+        // The copy records into a separate log, so that a refused batch leaves no entries behind.
+        let pending = self.log.as_ref().map(|_| TransactionLog::default());
+        let mut batch = Manager {
+            log: pending.clone(),
+            ..self.clone()
+        };
+
+        let applied = records
+            .iter()
+            .enumerate()
+            .map(|(i, record)| batch.apply_raw(record).map_err(|error| (i, error)))
+            .collect::<Result<Vec<()>, _>>()?;
+
+        if let (Some(log), Some(pending)) = (&self.log, pending) {
+            for entry in pending.entries() {
+                log.push(entry);
+            }
+        }
+
+        batch.log = self.log.take();
+        *self = batch;
+        Ok(applied)
+    }
+
+    /// Apply the records in ascending order of transaction ID, regardless of the order they arrived in.
+    /// The sort is stable, so records sharing a transaction ID, such as a deposit and its dispute,
+    /// keep their relative order. Unlike `load`, which streams records from the reader,
+    /// every record must be held in memory before the first is applied.
+    /// Soft errors are paired with the position of the record after sorting, numbered from 1.
+    /// The first fatal error, or any error in strict mode, halts processing as in `apply_all`.
+    #[inline]
+    pub fn apply_sorted_by_tx(
+        &mut self,
+        mut records: Vec<RawRecord>,
+        config: &Config,
+    ) -> ProcessResult {
+        let mut result = ProcessResult::default();
+        records.sort_by_key(|record| record.tx);
+
+        for (row, record) in (1..).zip(&records) {
+            result.processed += 1;
+            *result.operations.entry(record.operation).or_default() += 1;
+
+            let outcome = self
+                .count_client_op(record.client)
+                .and_then(|()| self.apply_raw(record));
+
+            if let Err(error) = outcome {
+                let halt = error.is_fatal() || config.strict;
+                result.skipped += 1;
+                result.soft_errors.push((row, error));
+
+                if halt {
+                    break;
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Create an empty account for the client ID before any funds arrive.
+    #[inline]
+    pub fn register_client(&mut self, client: u16) -> Result<(), TransactorError> {
+        let result = self.undoable(&[client], |mgr| match mgr.accounts.entry(client) {
+            Entry::Occupied(_) => Err(TransactorError::ClientAlreadyExists(client)),
+            Entry::Vacant(entry) => {
+                entry.insert(Account::new_empty());
+                Ok(())
+            }
+        });
+        self.log_applied(&result, LogEntry::new(Operation::Register, client, 0, None));
+        result
+    }
+
+    /// Freeze or unfreeze the account specified by the client ID administratively.
+    /// Recorded in the transaction log as an `AdminFreeze` or `AdminUnfreeze`.
+    #[inline]
+    pub fn set_client_frozen(&mut self, client: u16, frozen: bool) -> Result<(), TransactorError> {
+        let result = self.undoable(&[client], |mgr| {
+            mgr.account_mut(client).map(|acct| acct.set_frozen(frozen))
+        });
+
+        let operation = if frozen {
+            Operation::AdminFreeze
+        } else {
+            Operation::AdminUnfreeze
+        };
+        self.log_applied(&result, LogEntry::new(operation, client, 0, None));
+        result
+    }
+
+    /// Deposit funds into the account specified by the client ID, optionally tagged with a description.
+    /// A new account is only created if the deposit succeeds.
+    /// Deposits beyond the configured limit of tracked deposits, or exceeding the maximum amount, are refused.
+    #[inline]
+    #[instrument(level = "debug", skip(self))]
+    pub fn deposit(
+        &mut self,
+        client: u16,
+        tx: u32,
+        amt: u64,
+        description: Option<String>,
+    ) -> Result<(), TransactorError> {
+        let result = self.undoable(&[client], |mgr| {
+            mgr.try_deposit(client, tx, amt, description)
+        });
+        self.see_tx(&result, tx);
+        self.log_applied(
+            &result,
+            LogEntry::new(Operation::Deposit, client, tx, Some(amt)),
+        );
+        self.track(Operation::Deposit, result)
+    }
+
+    /// Deposit funds into many accounts in order, returning the outcome of each deposit in order,
+    /// such as when loading initial balances from a ledger dump. Room for new accounts is reserved
+    /// up front from the size hint of the deposits, rather than growing the map as accounts are created.
+    #[inline]
+    pub fn bulk_deposit(
+        &mut self,
+        deposits: impl IntoIterator<Item = (u16, u32, u64)>,
+    ) -> Vec<Result<(), TransactorError>> {
+        let deposits = deposits.into_iter();
+        self.accounts.reserve(deposits.size_hint().0);
+
+        deposits
+            .map(|(client, tx, amt)| self.deposit(client, tx, amt, None))
+            .collect()
+    }
+
+    /// Deposit funds into the account specified by the client ID, without counting it in the metrics.
+    #[inline]
+    fn try_deposit(
+        &mut self,
+        client: u16,
+        tx: u32,
+        amt: u64,
+        description: Option<String>,
+    ) -> Result<(), TransactorError> {
+        let (limit, max) = (self.max_deposits, self.max_amount);
+
+        match self.accounts.entry(client) {
+            Entry::Occupied(entry) => {
+                deposit_limited(entry.into_mut(), client, tx, amt, description, limit, max)
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(open_account(client, tx, amt, description, limit, max)?);
+                Ok(())
+            }
+        }
+    }
+
+    /// Deposit funds into many accounts in parallel, returning the outcome of each deposit in order.
+    /// Deposits are grouped by client ID, so that each account is only touched by a single thread,
+    /// and the deposits for each client are applied in order. New accounts are created in the order
+    /// of their first deposit, as if the deposits were applied sequentially.
+    /// When undo is enabled, the deposits are applied sequentially to record each snapshot.
+    #[cfg(feature = "parallel")]
+    pub fn par_deposit_all(
+        &mut self,
+        deposits: Vec<(u16, u32, u64)>,
+    ) -> Vec<Result<(), TransactorError>> {
+        if self.undo.is_enabled() {
+            return deposits
+                .into_iter()
+                .map(|(client, tx, amt)| self.deposit(client, tx, amt, None))
+                .collect();
+        }
+
+        let mut groups: IndexMap<u16, Vec<(usize, u32, u64)>> = IndexMap::new();
+        for (i, &(client, tx, amt)) in deposits.iter().enumerate() {
+            groups.entry(client).or_default().push((i, tx, amt));
+        }
+
+        let (limit, max) = (self.max_deposits, self.max_amount);
+        let apply = |client: u16, acct: &mut Account, group: &[(usize, u32, u64)], new: bool| {
+            group
+                .iter()
+                .map(|&(i, tx, amt)| {
+                    // As in `deposit`, a zero amount does not create the account of a new client.
+                    if new && amt == 0 && acct.deposit_count() == 0 {
+                        return (i, Err(TransactorError::ZeroAmount));
+                    }
+                    (i, deposit_limited(acct, client, tx, amt, None, limit, max))
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // Accounts for new clients are built separately, then inserted once every thread is done.
+        let created: Vec<_> = groups
+            .par_iter()
+            .filter(|(client, _)| !self.accounts.contains_key(*client))
+            .map(|(&client, group)| {
+                let mut acct = Account::new_empty();
+                let outcomes = apply(client, &mut acct, group, true);
+                (client, acct, outcomes)
+            })
+            .collect();
+
+        let mut outcomes: Vec<(usize, Result<(), TransactorError>)> = self
+            .accounts
+            .par_iter_mut()
+            .filter_map(|(&client, acct)| Some(apply(client, acct, groups.get(&client)?, false)))
+            .flatten()
+            .collect();
+
+        for (client, acct, group_outcomes) in created {
+            if group_outcomes.iter().any(|(_, outcome)| outcome.is_ok()) {
+                self.accounts.insert(client, acct);
+            }
+            outcomes.extend(group_outcomes);
+        }
+
+        outcomes.sort_unstable_by_key(|&(i, _)| i);
+        outcomes
+            .into_iter()
+            .zip(deposits)
+            .map(|((_, result), (client, tx, amt))| {
+                self.see_tx(&result, tx);
+                self.log_applied(
+                    &result,
+                    LogEntry::new(Operation::Deposit, client, tx, Some(amt)),
+                );
+                self.track(Operation::Deposit, result)
+            })
+            .collect()
+    }
+
+    /// Withdraw funds from the account specified by the client ID, tracking the withdrawal by its transaction ID.
+    /// Withdrawals exceeding the configured maximum amount are refused.
+    #[inline]
+    #[instrument(level = "debug", skip(self))]
+    pub fn withdraw(&mut self, client: u16, tx: u32, amt: u64) -> Result<(), TransactorError> {
+        let result = self.undoable(&[client], |mgr| {
+            check_amount(amt, mgr.max_amount)?;
+            mgr.account_mut(client)?.withdraw(tx, amt)
+        });
+        self.see_tx(&result, tx);
+        self.log_applied(
+            &result,
+            LogEntry::new(Operation::Withdrawal, client, tx, Some(amt)),
+        );
+        self.track(Operation::Withdrawal, result)
+    }
+
+    /// Transfer funds from one client's account to another's, crediting the target as a deposit.
+    /// If either side of the transfer fails, neither account is changed. Transfers to the same client are refused.
+    #[inline]
+    pub fn transfer(
+        &mut self,
+        from: u16,
+        to: u16,
+        tx: u32,
+        amt: u64,
+    ) -> Result<(), TransactorError> {
+        let result = self.undoable(&[from, to], |mgr| mgr.try_transfer(from, to, tx, amt));
+        self.see_tx(&result, tx);
+        self.log_applied(
+            &result,
+            LogEntry::new(Operation::Withdrawal, from, tx, Some(amt)),
+        );
+        self.log_applied(
+            &result,
+            LogEntry::new(Operation::Deposit, to, tx, Some(amt)),
+        );
+        result
+    }
+
+    /// Transfer funds between accounts, rolling back the debit if the credit fails.
+    #[inline]
+    fn try_transfer(
+        &mut self,
+        from: u16,
+        to: u16,
+        tx: u32,
+        amt: u64,
+    ) -> Result<(), TransactorError> {
+        if from == to {
+            return Err(TransactorError::SelfTransfer(from));
+        }
+
+        self.account_mut(from)
+            .and_then(|acct| acct.withdraw(tx, amt))
+            .map_err(|reason| TransactorError::transfer_failed(from, to, reason))?;
+
+        if let Err(reason) = self.try_deposit(to, tx, amt, None) {
+            // Roll back the debit so the failed transfer has no effect.
+            if let Some(acct) = self.accounts.get_mut(&from) {
+                acct.restore(tx)?;
+            }
+            return Err(TransactorError::transfer_failed(from, to, reason));
+        }
+
+        Ok(())
+    }
+
+    /// Charge a platform fee to the account specified by the client ID.
+    /// Fees are not tracked by the account, so the transaction ID is only recorded in the transaction log.
+    #[inline]
+    pub fn apply_fee(
+        &mut self,
+        client: u16,
+        tx: u32,
+        amt: u64,
+        apply_to_frozen: bool,
+    ) -> Result<(), TransactorError> {
+        let result = self.undoable(&[client], |mgr| {
+            mgr.account_mut(client)?.apply_fee(amt, apply_to_frozen)
+        });
+        self.see_tx(&result, tx);
+        self.log_applied(
+            &result,
+            LogEntry::new(Operation::Fee, client, tx, Some(amt)),
+        );
+        result
+    }
+
+    /// Dispute a transaction according to the client and transaction ID pair
+    #[inline]
+    #[instrument(level = "debug", skip(self))]
+    pub fn dispute(&mut self, client: u16, tx: u32) -> Result<(), TransactorError> {
+        let result = self.undoable(&[client], |mgr| mgr.account_mut(client)?.dispute(tx));
+        self.log_applied(&result, LogEntry::new(Operation::Dispute, client, tx, None));
+        self.track(Operation::Dispute, result)
+    }
+
+    /// Resolve a dispute according to the client and transaction ID pair
+    #[inline]
+    #[instrument(level = "debug", skip(self))]
+    pub fn resolve(&mut self, client: u16, tx: u32) -> Result<(), TransactorError> {
+        let result = self.undoable(&[client], |mgr| mgr.account_mut(client)?.resolve(tx));
+        self.log_applied(&result, LogEntry::new(Operation::Resolve, client, tx, None));
+        self.track(Operation::Resolve, result)
+    }
+
+    /// Chargeback a disputed transaction according to the client and transaction ID pair
+    #[inline]
+    #[instrument(level = "debug", skip(self))]
+    pub fn chargeback(&mut self, client: u16, tx: u32) -> Result<(), TransactorError> {
+        let result = self.undoable(&[client], |mgr| mgr.account_mut(client)?.chargeback(tx));
+        self.log_applied(
+            &result,
+            LogEntry::new(Operation::Chargeback, client, tx, None),
+        );
+        self.track(Operation::Chargeback, result)
+    }
+}
+
+impl IntoIterator for Manager {
+    type Item = (u16, Account);
+    type IntoIter = IntoIter<u16, Account>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.accounts.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Manager {
+    type Item = (&'a u16, &'a Account);
+    type IntoIter = Iter<'a, u16, Account>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.accounts.iter()
+    }
+}
+
+impl FromIterator<(u16, Account)> for Manager {
+    #[inline]
+    fn from_iter<I: IntoIterator<Item = (u16, Account)>>(iter: I) -> Self {
+        let mut manager = Manager::new();
+        manager.extend(iter);
+        manager
+    }
+}
+
+impl Extend<(u16, Account)> for Manager {
+    /// Insert each account, keeping the existing account if the client ID is already present.
+    /// The transaction IDs tracked by each inserted account count as seen.
+    #[inline]
+    fn extend<I: IntoIterator<Item = (u16, Account)>>(&mut self, iter: I) {
+        for (client, acct) in iter {
+            if let Entry::Vacant(entry) = self.accounts.entry(client) {
+                let max_tx = acct
+                    .deposit_iter()
+                    .map(|(tx, _)| tx)
+                    .chain(acct.withdrawal_iter().map(|(tx, _)| tx))
+                    .max();
+                self.max_seen_tx = self.max_seen_tx.max(max_tx.unwrap_or(0));
+                entry.insert(acct);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AccountDiff, Manager, MergeStrategy, RawRecord, RiskReport};
+    use crate::{
+        account::Account,
+        audit::AuditEntry,
+        config::Config,
+        error::TransactorError,
+        metrics::Metrics,
+        operation::Operation,
+        transaction_log::{LogEntry, TransactionLog},
+    };
+    use std::path::PathBuf;
+
+    fn validate_accounts(mgr: &Manager, clients: &[u16]) {
+        assert_eq!(mgr.accounts.len(), clients.len());
+
+        for client in clients {
+            assert!(mgr.accounts.contains_key(client));
+        }
+    }
+
+    #[test]
+    fn registration() {
+        let mut mgr = Manager::new();
+        mgr.deposit(1, 1, 100, None).expect("Failed to deposit");
+        mgr.deposit(2, 2, 200, None).expect("Failed to deposit");
+        mgr.deposit(3, 3, 300, None).expect("Failed to deposit");
+        mgr.deposit(4, 4, 400, None).expect("Failed to deposit");
+        validate_accounts(&mgr, &[1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn register_client() {
+        let mut mgr = Manager::new();
+        mgr.register_client(1).expect("Failed to register");
+        validate_accounts(&mgr, &[1]);
+        assert_eq!(mgr.accounts[&1].total(), 0);
+
+        mgr.deposit(1, 1, 100, None).expect("Failed to deposit");
+        validate_accounts(&mgr, &[1]);
+        assert_eq!(mgr.accounts[&1].available(), 100);
+    }
+
+    fn undo_manager(depth: usize) -> Manager {
+        Manager::from_config(&Config {
+            undo_depth: depth,
+            ..Config::default()
+        })
+    }
+
+    #[test]
+    fn undo() {
+        let mut mgr = undo_manager(4);
+        mgr.deposit(1, 1, 100, None).expect("Failed to deposit");
+        mgr.withdraw(1, 2, 40).expect("Failed to withdraw");
+        assert!(mgr.withdraw(1, 3, 100).is_err());
+
+        mgr.undo().expect("Failed to undo");
+        assert_eq!(mgr.accounts[&1].available(), 100);
+
+        mgr.undo().expect("Failed to undo");
+        validate_accounts(&mgr, &[]);
+
+        assert!(matches!(mgr.undo(), Err(TransactorError::NothingToUndo)));
+    }
+
+    #[test]
+    fn undo_multiple() {
+        let mut mgr = undo_manager(8);
+        mgr.deposit(1, 1, 100, None).expect("Failed to deposit");
+        mgr.deposit(2, 2, 50, None).expect("Failed to deposit");
+        mgr.transfer(1, 2, 3, 30).expect("Failed to transfer");
+        mgr.dispute(2, 2).expect("Failed to dispute");
+        mgr.chargeback(2, 2).expect("Failed to chargeback");
+        assert!(mgr.accounts[&2].is_frozen());
+
+        mgr.undo().expect("Failed to undo");
+        mgr.undo().expect("Failed to undo");
+        assert_eq!(mgr.accounts[&2].available(), 80);
+        assert!(!mgr.accounts[&2].is_frozen());
+
+        mgr.undo().expect("Failed to undo");
+        assert_eq!(mgr.accounts[&1].available(), 100);
+        assert_eq!(mgr.accounts[&2].available(), 50);
+        assert_eq!(mgr.accounts.keys().copied().collect::<Vec<_>>(), [1, 2]);
+    }
+
+    #[test]
+    fn undo_past_depth() {
+        let mut mgr = undo_manager(2);
+        for tx in 1..=4 {
+            mgr.deposit(1, tx, 100, None).expect("Failed to deposit");
+        }
+
+        mgr.undo().expect("Failed to undo");
+        mgr.undo().expect("Failed to undo");
+        assert_eq!(mgr.accounts[&1].available(), 200);
+
+        assert!(matches!(mgr.undo(), Err(TransactorError::NothingToUndo)));
+        assert_eq!(mgr.accounts[&1].available(), 200);
+    }
+
+    #[test]
+    fn undo_disabled() {
+        let mut mgr = Manager::new();
+        mgr.deposit(1, 1, 100, None).expect("Failed to deposit");
+
+        assert!(matches!(mgr.undo(), Err(TransactorError::NothingToUndo)));
+        validate_accounts(&mgr, &[1]);
+    }
+
+    #[test]
+    fn checkpoint_restore() {
+        let mut mgr = Manager::new();
+        mgr.deposit(1, 1, 100, None).expect("Failed to deposit");
+        mgr.checkpoint("start").expect("Failed to checkpoint");
+
+        mgr.dispute(1, 1).expect("Failed to dispute");
+        mgr.deposit(2, 2, 50, None).expect("Failed to deposit");
+        mgr.withdraw(2, 3, 40).expect("Failed to withdraw");
+
+        mgr.restore_checkpoint("start").expect("Failed to restore");
+        validate_accounts(&mgr, &[1]);
+        assert_eq!(mgr.accounts[&1].available(), 100);
+        assert_eq!(mgr.accounts[&1].held(), 0);
+
+        // The checkpoint may be restored repeatedly until dropped
+        mgr.deposit(2, 2, 50, None).expect("Failed to deposit");
+        mgr.restore_checkpoint("start").expect("Failed to restore");
+        validate_accounts(&mgr, &[1]);
+
+        mgr.drop_checkpoint("start");
+        assert!(matches!(
+            mgr.restore_checkpoint("start"),
+            Err(TransactorError::NoCheckpoint(ref name)) if name == "start"
+        ));
+    }
+
+    #[test]
+    fn replay_log() {
+        let (mut mgr, log) = Manager::with_log();
+
+        // Each transaction belongs to the client of the same index
+        let owner = |tx: u32| (tx % 7) as u16;
+
+        for i in 0..100u32 {
+            let _ = match i % 10 {
+                0..=3 => mgr.deposit(owner(i), i, u64::from(i) * 100, None),
+                4 if i % 20 == 4 => mgr.withdraw(owner(i), i, 250),
+                4 => mgr.apply_fee(owner(i), i, 10, true),
+                5 | 6 => mgr.dispute(owner(i - 5), i - 5),
+                7 => mgr.resolve(owner(i - 7), i - 7),
+                8 => mgr.chargeback(owner(i - 7), i - 7),
+                _ => mgr.transfer(owner(i), owner(i + 1), i, 150),
+            };
+        }
+
+        let entries = log.entries();
+        for operation in [Operation::Chargeback, Operation::Fee, Operation::Deposit] {
+            assert!(entries.iter().any(|entry| entry.operation == operation));
+        }
+
+        let replayed = log.replay().expect("Failed to replay");
+        assert_eq!(replayed.accounts, mgr.accounts);
+    }
+
+    #[test]
+    fn replay_refused() {
+        let log = TransactionLog::default();
+        log.push(LogEntry::new(Operation::Deposit, 1, 1, Some(100)));
+        log.push(LogEntry::new(Operation::Withdrawal, 1, 2, Some(150)));
+
+        assert!(matches!(
+            log.replay(),
+            Err((1, TransactorError::WithdrawalExceedsAvailable { .. }))
+        ));
+    }
+
+    #[test]
+    fn log_refuses_state_replacement() {
+        let (mut mgr, log) = Manager::with_log();
+        mgr.deposit(1, 1, 100, None).expect("Failed to deposit");
+        mgr.checkpoint("start").expect("Failed to checkpoint");
+        mgr.deposit(1, 2, 100, None).expect("Failed to deposit");
+
+        let refused = [
+            ("restore_checkpoint", mgr.restore_checkpoint("start")),
+            (
+                "merge",
+                mgr.merge(Manager::new(), MergeStrategy::SkipConflicts),
+            ),
+            ("retain", mgr.retain(|_, _| false)),
+            (
+                "remove_empty_accounts",
+                mgr.remove_empty_accounts().map(|_| ()),
+            ),
+            ("close_account", mgr.close_account(1)),
+            ("undo", mgr.undo()),
+        ];
+        for (name, result) in refused {
+            assert!(
+                matches!(result, Err(TransactorError::NotReplayable(ref op)) if op == name),
+                "{name}: {result:?}"
+            );
+        }
+
+        let replayed = log.replay().expect("Failed to replay");
+        assert_eq!(replayed.accounts, mgr.accounts);
+        assert_eq!(replayed.accounts[&1].available(), 200);
+    }
+
+    #[test]
+    fn replay_admin_freeze() {
+        let (mut mgr, log) = Manager::with_log();
+        mgr.deposit(1, 1, 100, None).expect("Failed to deposit");
+        mgr.deposit(2, 2, 100, None).expect("Failed to deposit");
+        mgr.set_client_frozen(1, true).expect("Failed to freeze");
+        mgr.set_client_frozen(2, true).expect("Failed to freeze");
+        mgr.set_client_frozen(2, false).expect("Failed to unfreeze");
+
+        let replayed = log.replay().expect("Failed to replay");
+        assert!(replayed.accounts[&1].is_frozen());
+        assert!(!replayed.accounts[&2].is_frozen());
+        assert_eq!(replayed.accounts, mgr.accounts);
+    }
+
+    #[test]
+    fn diff() {
+        let mut before = Manager::new();
+        before.deposit(1, 1, 100, None).expect("Failed to deposit");
+        before.deposit(2, 2, 200, None).expect("Failed to deposit");
+        before.deposit(3, 3, 300, None).expect("Failed to deposit");
+
+        let mut after: Manager = before
+            .accounts
+            .iter()
+            .map(|(&client, acct)| (client, acct.clone()))
+            .collect();
+        after.withdraw(1, 5, 40).expect("Failed to withdraw");
+        after.dispute(1, 1).unwrap_err();
+        after.deposit(2, 4, 50, None).expect("Failed to deposit");
+        after.dispute(2, 2).expect("Failed to dispute");
+        after.chargeback(2, 2).expect("Failed to chargeback");
+        after.deposit(4, 5, 75, None).expect("Failed to deposit");
+
+        assert_eq!(
+            Manager::diff(&before, &after),
+            [
+                AccountDiff {
+                    client: 1,
+                    available_delta: -40,
+                    held_delta: 0,
+                    newly_frozen: false,
+                },
+                AccountDiff {
+                    client: 2,
+                    available_delta: -150,
+                    held_delta: 0,
+                    newly_frozen: true,
+                },
+                AccountDiff {
+                    client: 4,
+                    available_delta: 75,
+                    held_delta: 0,
+                    newly_frozen: false,
+                },
+            ]
+        );
+        assert!(Manager::diff(&after, &after).is_empty());
+    }
+
+    #[test]
+    fn risk_report() {
+        let mut mgr = Manager::new();
+        assert_eq!(mgr.risk_report(), RiskReport::default());
+
+        // Client 1 has two disputed deposits, one later resolved
+        mgr.deposit(1, 1, 100, None).expect("Failed to deposit");
+        mgr.deposit(1, 2, 300, None).expect("Failed to deposit");
+        mgr.dispute(1, 1).expect("Failed to dispute");
+        mgr.dispute(1, 2).expect("Failed to dispute");
+        mgr.resolve(1, 2).expect("Failed to resolve");
+
+        // Client 2 has no disputes
+        mgr.deposit(2, 3, 1000, None).expect("Failed to deposit");
+
+        // Client 3 has the largest exposure across two disputed deposits
+        mgr.deposit(3, 4, 150, None).expect("Failed to deposit");
+        mgr.deposit(3, 5, 200, None).expect("Failed to deposit");
+        mgr.dispute(3, 4).expect("Failed to dispute");
+        mgr.dispute(3, 5).expect("Failed to dispute");
+
+        // Client 4 has a charged back deposit, no longer at risk
+        mgr.deposit(4, 6, 500, None).expect("Failed to deposit");
+        mgr.dispute(4, 6).expect("Failed to dispute");
+        mgr.chargeback(4, 6).expect("Failed to chargeback");
+
+        assert_eq!(mgr.portfolio_at_risk(), Some(450));
+        assert_eq!(
+            mgr.risk_report(),
+            RiskReport {
+                total_at_risk: 450,
+                accounts_with_disputes: 2,
+                highest_single_dispute: 200,
+                client_with_highest_exposure: Some(3),
+            }
+        );
+    }
+
+    #[test]
+    fn portfolio_at_risk_overflow() {
+        let mut mgr = Manager::new();
+        for client in [1, 2] {
+            mgr.deposit(client, u32::from(client), u64::MAX, None)
+                .expect("Failed to deposit");
+            mgr.dispute(client, u32::from(client))
+                .expect("Failed to dispute");
+        }
+
+        assert_eq!(mgr.portfolio_at_risk(), None);
+        assert_eq!(mgr.risk_report().total_at_risk, u64::MAX);
+    }
+
+    #[test]
+    fn accounts_iter() {
+        let mut mgr = Manager::new();
+        for client in [3, 1, 2] {
+            mgr.deposit(client, u32::from(client), u64::from(client) * 100, None)
+                .expect("Failed to deposit");
+        }
+
+        let accounts: Vec<(u16, u64)> = mgr
+            .accounts_iter()
+            .map(|(client, acct)| (client, acct.available()))
+            .collect();
+        assert_eq!(accounts, [(3, 300), (1, 100), (2, 200)]);
+
+        for (client, acct) in mgr.accounts_iter_mut() {
+            acct.set_frozen(client != 1);
+        }
+        let frozen: Vec<u16> = mgr
+            .accounts_iter()
+            .filter(|(_, acct)| acct.is_frozen())
+            .map(|(client, _)| client)
+            .collect();
+        assert_eq!(frozen, [3, 2]);
+    }
+
+    #[test]
+    fn clients_with_disputes() {
+        let mut mgr = Manager::new();
+        for client in 1..=5 {
+            mgr.deposit(client, u32::from(client), 100, None)
+                .expect("Failed to deposit");
+        }
+        assert_eq!(mgr.clients_with_disputes().count(), 0);
+
+        mgr.dispute(4, 4).expect("Failed to dispute");
+        mgr.dispute(2, 2).expect("Failed to dispute");
+
+        let clients: Vec<u16> = mgr
+            .clients_with_disputes()
+            .map(|(&client, acct)| {
+                assert_eq!(acct.held(), 100);
+                client
+            })
+            .collect();
+        assert_eq!(clients, [2, 4]);
+    }
+
+    #[test]
+    fn pending_disputes_report() {
+        let mut mgr = Manager::new();
+        assert!(mgr.pending_disputes_report().is_empty());
+
+        for (client, tx, amt) in [
+            (3, 1, 100),
+            (1, 5, 200),
+            (1, 2, 300),
+            (2, 3, 400),
+            (3, 4, 500),
+        ] {
+            mgr.deposit(client, tx, amt, None)
+                .expect("Failed to deposit");
+        }
+        for (client, tx) in [(3, 4), (1, 5), (3, 1), (1, 2), (2, 3)] {
+            mgr.dispute(client, tx).expect("Failed to dispute");
+        }
+        mgr.resolve(2, 3).expect("Failed to resolve");
+
+        assert_eq!(
+            mgr.pending_disputes_report(),
+            [(1, 2, 300), (1, 5, 200), (3, 1, 100), (3, 4, 500)]
+        );
+
+        mgr.chargeback(3, 1).expect("Failed to chargeback");
+        assert_eq!(
+            mgr.pending_disputes_report(),
+            [(1, 2, 300), (1, 5, 200), (3, 4, 500)]
+        );
+    }
+
+    #[test]
+    fn inspect_clients() {
+        let mut mgr = Manager::new();
+        assert!(mgr.is_empty());
+        assert_eq!(mgr.account_count(), 0);
+        assert_eq!(mgr.client_ids().next(), None);
+
+        mgr.deposit(3, 1, 100, None).expect("Failed to deposit");
+        mgr.deposit(1, 2, 100, None).expect("Failed to deposit");
+        mgr.deposit(3, 3, 100, None).expect("Failed to deposit");
+        assert!(!mgr.is_empty());
+        assert_eq!(mgr.account_count(), 2);
+        assert_eq!(mgr.client_ids().collect::<Vec<_>>(), [3, 1]);
+
+        // Frozen accounts remain registered
+        mgr.dispute(1, 2).expect("Failed to dispute");
+        mgr.chargeback(1, 2).expect("Failed to chargeback");
+        assert_eq!(mgr.account_count(), 2);
+        assert_eq!(mgr.client_ids().collect::<Vec<_>>(), [3, 1]);
+
+        // Refused operations do not create an account
+        mgr.deposit(2, 1, 100, None).expect("Failed to deposit");
+        mgr.deposit(4, 1, 100, None).expect("Failed to deposit");
+        mgr.withdraw(5, 2, 100).unwrap_err();
+        assert_eq!(mgr.client_ids().collect::<Vec<_>>(), [3, 1, 2, 4]);
+
+        mgr.retain(|client, _| client > 2)
+            .expect("Failed to retain");
+        assert_eq!(mgr.account_count(), 2);
+        assert_eq!(mgr.client_ids().collect::<Vec<_>>(), [3, 4]);
+    }
+
+    #[test]
+    fn register_existing_client() {
+        let mut mgr = Manager::new();
+        mgr.register_client(1).expect("Failed to register");
+        mgr.deposit(1, 1, 100, None).expect("Failed to deposit");
+
+        assert!(matches!(
+            mgr.register_client(1),
+            Err(TransactorError::ClientAlreadyExists(1))
+        ));
+        assert_eq!(mgr.accounts[&1].available(), 100);
+    }
+
+    #[test]
+    fn deposit_limit() {
+        let config = Config {
+            max_deposits_per_account: Some(2),
+            ..Config::default()
+        };
+        let mut mgr = Manager::from_config(&config);
+        mgr.deposit(1, 1, 100, None).expect("Failed to deposit");
+        mgr.deposit(1, 2, 100, None).expect("Failed to deposit");
+        mgr.withdraw(1, 3, 50).expect("Failed to withdraw");
+
+        assert!(matches!(
+            mgr.deposit(1, 3, 100, None),
+            Err(TransactorError::DepositLimitExceeded {
+                client: 1,
+                limit: 2
+            })
+        ));
+        assert_eq!(mgr.accounts[&1].available(), 150);
+        assert_eq!(mgr.accounts[&1].deposit_count(), 2);
+
+        mgr.deposit(2, 4, 100, None).expect("Failed to deposit");
+        validate_accounts(&mgr, &[1, 2]);
+    }
+
+    #[test]
+    fn max_transaction_amount() {
+        let config = Config {
+            max_transaction_amount: Some(1000),
+            ..Config::default()
+        };
+        let mut mgr = Manager::from_config(&config);
+        mgr.deposit(1, 1, 999, None).expect("Failed to deposit");
+        mgr.deposit(1, 2, 1000, None).expect("Failed to deposit");
+        assert!(matches!(
+            mgr.deposit(1, 3, 1001, None),
+            Err(TransactorError::AmountTooLarge {
+                amount: 1001,
+                max: 1000
+            })
+        ));
+        assert!(matches!(
+            mgr.deposit(2, 4, 1001, None),
+            Err(TransactorError::AmountTooLarge { .. })
+        ));
+        assert_eq!(mgr.accounts[&1].available(), 1999);
+        assert_eq!(mgr.accounts[&1].deposit_count(), 2);
+        assert!(!mgr.accounts.contains_key(&2));
+
+        mgr.withdraw(1, 6, 999).expect("Failed to withdraw");
+        mgr.withdraw(1, 7, 1000).expect("Failed to withdraw");
+        mgr.deposit(1, 5, 1000, None).expect("Failed to deposit");
+        assert!(matches!(
+            mgr.withdraw(1, 8, 1001),
+            Err(TransactorError::AmountTooLarge { .. })
+        ));
+        assert_eq!(mgr.accounts[&1].available(), 1000);
+    }
+
+    #[test]
+    fn deposit_limit_zero() {
+        let config = Config {
+            max_deposits_per_account: Some(0),
+            ..Config::default()
+        };
+        let mut mgr = Manager::from_config(&config);
+
+        assert!(matches!(
+            mgr.deposit(1, 1, 100, None),
+            Err(TransactorError::DepositLimitExceeded {
+                client: 1,
+                limit: 0
+            })
+        ));
+        validate_accounts(&mgr, &[]);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn par_deposit_all() {
+        let config = Config {
+            max_deposits_per_account: Some(2),
+            ..Config::default()
+        };
+        let mut seq = Manager::from_config(&config);
+        seq.deposit(3, 1, 100, None).expect("Failed to deposit");
+        let mut par = Manager::from_config(&config);
+        par.deposit(3, 1, 100, None).expect("Failed to deposit");
+
+        // Client 3 exceeds the deposit limit, client 5 is created before client 4,
+        // and client 6 is only created by its first non-zero deposit
+        let deposits = vec![
+            (5, 1, 100),
+            (3, 2, 100),
+            (4, 3, 100),
+            (3, 4, 100),
+            (5, 5, 100),
+            (6, 8, 0),
+            (5, 6, 100),
+            (4, 7, 100),
+            (6, 9, 100),
+        ];
+        let expected: Vec<_> = deposits
+            .iter()
+            .map(|&(client, tx, amt)| seq.deposit(client, tx, amt, None))
+            .collect();
+        let results = par.par_deposit_all(deposits);
+
+        assert_eq!(format!("{results:?}"), format!("{expected:?}"));
+        assert!(matches!(
+            results[3],
+            Err(TransactorError::DepositLimitExceeded {
+                client: 3,
+                limit: 2
+            })
+        ));
+        assert_eq!(
+            seq.into_iter().collect::<Vec<_>>(),
+            par.into_iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn count_client_op() {
+        let mut mgr = Manager::new();
+        for _ in 0..10 {
+            mgr.count_client_op(1)
+                .expect("Unlimited operations refused");
+        }
+
+        let mut mgr = Manager::from_config(&Config {
+            max_ops_per_client: Some(3),
+            ..Config::default()
+        });
+        for _ in 0..3 {
+            mgr.count_client_op(1).expect("Operation refused");
+        }
+        mgr.count_client_op(2).expect("Operation refused");
+
+        for _ in 0..2 {
+            assert!(matches!(
+                mgr.count_client_op(1),
+                Err(TransactorError::ClientRateLimited(1))
+            ));
+        }
+    }
+
+    #[test]
+    fn bulk_deposit() {
+        let mut mgr = Manager::new();
+        let results = mgr.bulk_deposit([(2, 1, 100), (1, 2, 200), (2, 1, 300), (2, 3, 400)]);
+
+        assert_eq!(results.len(), 4);
+        assert!(matches!(
+            results[..],
+            [
+                Ok(()),
+                Ok(()),
+                Err(TransactorError::DuplicateTxn(1)),
+                Ok(())
+            ]
+        ));
+        assert_eq!(mgr.client_ids().collect::<Vec<_>>(), [2, 1]);
+        assert_eq!(mgr.accounts[&2].available(), 500);
+        assert_eq!(mgr.accounts[&1].available(), 200);
+
+        assert!(mgr.bulk_deposit(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn deposit_zero_new_account() {
+        let mut mgr = Manager::new();
+        assert!(matches!(
+            mgr.deposit(1, 1, 0, None),
+            Err(TransactorError::ZeroAmount)
+        ));
+        validate_accounts(&mgr, &[]);
+
+        mgr.deposit(1, 1, 100, None).expect("Failed to deposit");
+        assert_eq!(mgr.accounts[&1].deposit_count(), 1);
+        assert_eq!(mgr.accounts[&1].available(), 100);
+    }
+
+    #[test]
+    fn deposit_withdrawal() {
+        let mut mgr = Manager::new();
+        mgr.deposit(1, 1, 100, None).expect("Failed to deposit");
+        mgr.deposit(2, 2, 200, None).expect("Failed to deposit");
+        mgr.withdraw(1, 3, 50).expect("Failed to withdrawal");
+        mgr.withdraw(2, 4, 100).expect("Failed to withdrawal");
+        mgr.deposit(1, 5, 100, None).expect("Failed to deposit");
+        validate_accounts(&mgr, &[1, 2]);
+
+        assert_eq!(mgr.accounts[&1].available(), 150);
+        assert_eq!(mgr.accounts[&1].held(), 0);
+        assert_eq!(mgr.accounts[&2].available(), 100);
+        assert_eq!(mgr.accounts[&2].held(), 0);
+    }
+
+    #[test]
+    fn apply_record() {
+        let mut mgr = Manager::new();
+        mgr.apply_record(Operation::Deposit, 1, 1, Some(100))
+            .expect("Failed to deposit");
+        mgr.apply_record(Operation::Deposit, 1, 2, Some(50))
+            .expect("Failed to deposit");
+        mgr.apply_record(Operation::Withdrawal, 1, 3, Some(30))
+            .expect("Failed to withdraw");
+        mgr.apply_record(Operation::Fee, 1, 4, Some(20))
+            .expect("Failed to apply fee");
+        assert_eq!(mgr.accounts[&1].available(), 100);
+
+        mgr.apply_record(Operation::Dispute, 1, 1, None)
+            .expect("Failed to dispute");
+        assert_eq!(mgr.accounts[&1].available(), 0);
+        assert_eq!(mgr.accounts[&1].held(), 100);
+
+        mgr.apply_record(Operation::Resolve, 1, 1, None)
+            .expect("Failed to resolve");
+        assert_eq!(mgr.accounts[&1].available(), 100);
+        assert_eq!(mgr.accounts[&1].held(), 0);
+
+        mgr.apply_record(Operation::Dispute, 1, 2, None)
+            .expect("Failed to dispute");
+        mgr.apply_record(Operation::Chargeback, 1, 2, None)
+            .expect("Failed to chargeback");
+        assert_eq!(mgr.accounts[&1].available(), 50);
+        assert!(mgr.accounts[&1].is_frozen());
+    }
+
+    #[test]
+    fn apply_record_errors() {
+        let mut mgr = Manager::new();
 
-/// Account manager associating a client ID to an account.
-#[derive(Debug)]
-pub struct Manager {
-    accounts: HashMap<u16, Account>,
-}
+        for op in [Operation::Deposit, Operation::Withdrawal, Operation::Fee] {
+            assert!(matches!(
+                mgr.apply_record(op, 1, 1, None),
+                Err(TransactorError::MissingAmount)
+            ));
+        }
 
-impl Default for Manager {
-    #[inline]
-    fn default() -> Self {
-        Manager::new()
+        assert!(matches!(
+            mgr.apply_record(Operation::Transfer, 1, 1, Some(100)),
+            Err(TransactorError::MissingTarget)
+        ));
+        assert!(matches!(
+            mgr.apply_record(Operation::Dispute, 1, 1, None),
+            Err(TransactorError::NoClient(1))
+        ));
+        validate_accounts(&mgr, &[]);
     }
-}
 
-impl Manager {
-    /// Construct a new `Manager`.
-    #[inline]
-    pub fn new() -> Self {
-        Manager {
-            accounts: HashMap::new(),
-        }
+    #[test]
+    fn apply_batch_atomic() {
+        let (mut mgr, log) = Manager::with_log();
+        mgr.deposit(1, 1, 100, None).expect("Failed to deposit");
+
+        let batch = [
+            RawRecord {
+                target: Some(2),
+                ..RawRecord::new(Operation::Transfer, 1, 2, Some(40))
+            },
+            RawRecord::new(Operation::Deposit, 2, 3, Some(10)),
+        ];
+        let applied = mgr.apply_batch_atomic(&batch).expect("Failed to apply");
+
+        assert_eq!(applied.len(), 2);
+        assert_eq!(mgr.accounts[&1].available(), 60);
+        assert_eq!(mgr.accounts[&2].available(), 50);
+        assert_eq!(log.len(), 4);
     }
 
-    /// Deposit funds into the account specified by the client ID.
-    #[inline]
-    pub fn deposit(&mut self, client: u16, tx: u32, amt: u64) -> Result<(), TransactorError> {
-        if let Some(acct) = self.accounts.get_mut(&client) {
-            acct.deposit(tx, amt)?;
-            return Ok(());
-        }
+    #[test]
+    fn apply_batch_atomic_rollback() {
+        let (mut mgr, log) = Manager::with_log();
+        mgr.deposit(1, 1, 100, None).expect("Failed to deposit");
 
-        let acct = Account::new(tx, amt);
-        self.accounts.insert(client, acct);
+        let batch = [
+            RawRecord::new(Operation::Deposit, 2, 2, Some(50)),
+            RawRecord::new(Operation::Withdrawal, 1, 4, Some(30)),
+            RawRecord::new(Operation::Withdrawal, 1, 5, Some(80)),
+            RawRecord::new(Operation::Deposit, 3, 3, Some(10)),
+        ];
+        let result = mgr.apply_batch_atomic(&batch);
 
-        Ok(())
+        assert!(matches!(
+            result,
+            Err((
+                2,
+                TransactorError::WithdrawalExceedsAvailable {
+                    available: 70,
+                    attempted: 80
+                }
+            ))
+        ));
+        validate_accounts(&mgr, &[1]);
+        assert_eq!(mgr.accounts[&1].available(), 100);
+        assert_eq!(log.len(), 1);
     }
 
-    /// Withdraw funds from the account specified by the client ID.
-    #[inline]
-    pub fn withdraw(&mut self, client: u16, amt: u64) -> Result<(), TransactorError> {
-        let account = self
-            .accounts
-            .get_mut(&client)
-            .ok_or(TransactorError::NoClient(client))?;
-        account.withdraw(amt)?;
+    #[test]
+    fn apply_sorted_by_tx() {
+        let records = vec![
+            RawRecord::new(Operation::Resolve, 1, 9, None),
+            RawRecord::new(Operation::Withdrawal, 1, 3, Some(60)),
+            RawRecord::new(Operation::Deposit, 1, 1, Some(100)),
+            RawRecord::new(Operation::Deposit, 1, 2, Some(50)),
+            RawRecord::new(Operation::Dispute, 1, 2, None),
+        ];
 
-        Ok(())
+        // Applied as they arrived, the withdrawal precedes any deposit
+        let mut sequential = Manager::new();
+        for record in &records {
+            sequential.apply_raw(record).ok();
+        }
+        assert_eq!(sequential.accounts[&1].available(), 100);
+
+        let mut mgr = Manager::new();
+        let result = mgr.apply_sorted_by_tx(records.clone(), &Config::default());
+
+        assert_eq!(result.processed, 5);
+        assert_eq!(result.skipped, 1);
+        assert!(matches!(
+            result.soft_errors[..],
+            [(5, TransactorError::NoTransaction(9))]
+        ));
+        assert_eq!(result.operations[&Operation::Deposit], 2);
+        validate_accounts(&mgr, &[1]);
+        assert_eq!(mgr.accounts[&1].available(), 40);
+        assert_eq!(mgr.accounts[&1].held(), 50);
+        assert_ne!(mgr.accounts[&1], sequential.accounts[&1]);
+
+        // Strict mode halts on the first soft error
+        let config = Config {
+            strict: true,
+            ..Config::default()
+        };
+        let mut records = records;
+        records.push(RawRecord::new(Operation::Withdrawal, 1, 4, Some(1000)));
+        let result = Manager::new().apply_sorted_by_tx(records, &config);
+        assert_eq!(result.processed, 5);
+        assert!(matches!(
+            result.soft_errors[..],
+            [(5, TransactorError::WithdrawalExceedsAvailable { .. })]
+        ));
     }
 
-    /// Dispute a transaction according to the client and transaction ID pair
-    #[inline]
-    pub fn dispute(&mut self, client: u16, tx: u32) -> Result<(), TransactorError> {
-        let account = self
-            .accounts
-            .get_mut(&client)
-            .ok_or(TransactorError::NoClient(client))?;
+    #[test]
+    fn insertion_order() {
+        let clients = [7, 3, 9, 1, 5];
+        let mut mgr = Manager::new();
+        for client in clients {
+            mgr.deposit(client, u32::from(client), 100, None)
+                .expect("Failed to deposit");
+        }
+        mgr.deposit(9, 10, 100, None).expect("Failed to deposit");
 
-        account.dispute(tx)?;
+        let order: Vec<u16> = (&mgr).into_iter().map(|(&client, _)| client).collect();
+        assert_eq!(order, clients);
 
-        Ok(())
+        let order: Vec<u16> = mgr.into_iter().map(|(client, _)| client).collect();
+        assert_eq!(order, clients);
     }
 
-    /// Resolve a dispute according to the client and transaction ID pair
-    #[inline]
-    pub fn resolve(&mut self, client: u16, tx: u32) -> Result<(), TransactorError> {
-        let account = self
-            .accounts
-            .get_mut(&client)
-            .ok_or(TransactorError::NoClient(client))?;
-        account.resolve(tx)?;
+    #[test]
+    fn drain_into_sorted_vec() {
+        let mut mgr = Manager::new();
+        for client in (1..=5).rev() {
+            mgr.deposit(client, u32::from(client), 100, None)
+                .expect("Failed to deposit");
+        }
 
-        Ok(())
+        let clients: Vec<u16> = mgr
+            .drain_into_sorted_vec()
+            .into_iter()
+            .map(|(client, _)| client)
+            .collect();
+        assert_eq!(clients, [1, 2, 3, 4, 5]);
     }
 
-    /// Chargeback a disputed transaction according to the client and transaction ID pair
-    #[inline]
-    pub fn chargeback(&mut self, client: u16, tx: u32) -> Result<(), TransactorError> {
-        let account = self
-            .accounts
-            .get_mut(&client)
-            .ok_or(TransactorError::NoClient(client))?;
-        account.chargeback(tx)?;
+    #[test]
+    fn into_sorted_vec_by() {
+        let mut mgr = Manager::new();
+        for client in (1..=5).rev() {
+            mgr.deposit(client, u32::from(client), u64::from(client) * 100, None)
+                .expect("Failed to deposit");
+        }
 
-        Ok(())
+        let by_available =
+            mgr.into_sorted_vec_by(|(_, a), (_, b)| b.available().cmp(&a.available()));
+        let clients: Vec<u16> = by_available.iter().map(|&(client, _)| client).collect();
+        assert_eq!(clients, [5, 4, 3, 2, 1]);
+        assert_eq!(by_available[0].1.available(), 500);
     }
-}
 
-impl IntoIterator for Manager {
-    type Item = (u16, Account);
-    type IntoIter = IntoIter<u16, Account>;
+    #[test]
+    fn metrics() {
+        let mut mgr = Manager::with_metrics();
+        mgr.deposit(1, 1, 100, None).expect("Failed to deposit");
+        mgr.deposit(1, 2, 100, None).expect("Failed to deposit");
+        assert!(mgr.deposit(1, 2, 100, None).is_err());
+        mgr.withdraw(1, 3, 50).expect("Failed to withdraw");
+        assert!(mgr.withdraw(2, 4, 50).is_err());
+        mgr.dispute(1, 1).expect("Failed to dispute");
+        assert!(mgr.dispute(1, 1).is_err());
+        mgr.resolve(1, 1).expect("Failed to resolve");
+        assert!(mgr.resolve(1, 1).is_err());
+        mgr.dispute(1, 2).expect("Failed to dispute");
+        mgr.chargeback(1, 2).expect("Failed to chargeback");
+        assert!(mgr.chargeback(1, 2).is_err());
 
-    #[inline]
-    fn into_iter(self) -> Self::IntoIter {
-        self.accounts.into_iter()
+        // Transfers are not counted as their constituent withdrawal and deposit
+        assert!(mgr.transfer(3, 1, 3, 10).is_err());
+
+        let metrics = mgr.metrics().expect("No Metrics");
+        assert_eq!(
+            *metrics,
+            Metrics {
+                deposits_ok: 2,
+                deposits_err: 1,
+                withdrawals_ok: 1,
+                withdrawals_err: 1,
+                disputes_ok: 2,
+                disputes_err: 1,
+                resolves_ok: 1,
+                resolves_err: 1,
+                chargebacks_ok: 1,
+                chargebacks_err: 1,
+            }
+        );
     }
-}
 
-impl<'a> IntoIterator for &'a Manager {
-    type Item = (&'a u16, &'a Account);
-    type IntoIter = Iter<'a, u16, Account>;
+    #[test]
+    fn metrics_disabled() {
+        let mut mgr = Manager::new();
+        mgr.deposit(1, 1, 100, None).expect("Failed to deposit");
 
-    #[inline]
-    fn into_iter(self) -> Self::IntoIter {
-        self.accounts.iter()
+        assert!(mgr.metrics().is_none());
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::Manager;
-    use crate::error::TransactorError;
+    #[test]
+    fn transfer() {
+        let mut mgr = Manager::new();
+        mgr.deposit(1, 1, 100, None).expect("Failed to deposit");
+        mgr.transfer(1, 2, 2, 40).expect("Failed to transfer");
+        validate_accounts(&mgr, &[1, 2]);
 
-    fn validate_accounts(mgr: &Manager, clients: &[u16]) {
-        assert_eq!(mgr.accounts.len(), clients.len());
+        assert_eq!(mgr.accounts[&1].available(), 60);
+        assert_eq!(mgr.accounts[&2].available(), 40);
 
-        for client in clients {
-            assert!(mgr.accounts.contains_key(client));
-        }
+        let result = mgr.transfer(1, 2, 3, 100);
+        assert!(matches!(
+            result,
+            Err(TransactorError::TransferFailed { from: 1, to: 2, reason })
+                if matches!(*reason, TransactorError::WithdrawalExceedsAvailable { .. })
+        ));
+        assert_eq!(mgr.accounts[&1].available(), 60);
+        assert_eq!(mgr.accounts[&2].available(), 40);
     }
 
     #[test]
-    fn registration() {
+    fn self_transfer() {
         let mut mgr = Manager::new();
-        mgr.deposit(1, 1, 100).expect("Failed to deposit");
-        mgr.deposit(2, 2, 200).expect("Failed to deposit");
-        mgr.deposit(3, 3, 300).expect("Failed to deposit");
-        mgr.deposit(4, 4, 400).expect("Failed to deposit");
-        validate_accounts(&mgr, &[1, 2, 3, 4]);
+        mgr.deposit(1, 1, 100, None).expect("Failed to deposit");
+
+        assert!(matches!(
+            mgr.transfer(1, 1, 2, 40),
+            Err(TransactorError::SelfTransfer(1))
+        ));
+        assert_eq!(mgr.accounts[&1].available(), 100);
+        assert_eq!(mgr.accounts[&1].withdrawal_iter().count(), 0);
+        assert_eq!(mgr.next_available_tx_id(), Some(2));
     }
 
     #[test]
-    fn deposit_withdrawal() {
+    fn transfer_rollback() {
         let mut mgr = Manager::new();
-        mgr.deposit(1, 1, 100).expect("Failed to deposit");
-        mgr.deposit(2, 2, 200).expect("Failed to deposit");
-        mgr.withdraw(1, 50).expect("Failed to withdrawal");
-        mgr.withdraw(2, 100).expect("Failed to withdrawal");
-        mgr.deposit(1, 5, 100).expect("Failed to deposit");
-        validate_accounts(&mgr, &[1, 2]);
+        mgr.deposit(1, 1, 100, None).expect("Failed to deposit");
+        mgr.deposit(2, 2, 100, None).expect("Failed to deposit");
 
-        assert_eq!(mgr.accounts[&1].available(), 150);
-        assert_eq!(mgr.accounts[&1].held(), 0);
+        // The credit fails as transaction 2 already exists for client 2
+        let result = mgr.transfer(1, 2, 2, 40);
+        assert!(matches!(
+            result,
+            Err(TransactorError::TransferFailed { from: 1, to: 2, reason })
+                if matches!(*reason, TransactorError::DuplicateTxn(2))
+        ));
+        assert_eq!(mgr.accounts[&1].available(), 100);
         assert_eq!(mgr.accounts[&2].available(), 100);
-        assert_eq!(mgr.accounts[&2].held(), 0);
+    }
+
+    #[test]
+    fn transfer_overflow_rollback() {
+        let mut mgr = Manager::new();
+        mgr.deposit(1, 1, 100, None).expect("Failed to deposit");
+        mgr.deposit(2, 2, u64::MAX - 10, None)
+            .expect("Failed to deposit");
+
+        // The credit fails as the funds of client 2 would overflow
+        let result = mgr.transfer(1, 2, 3, 40);
+        assert!(matches!(
+            result,
+            Err(TransactorError::TransferFailed { from: 1, to: 2, reason })
+                if matches!(*reason, TransactorError::BalanceOverflow { tx: 3 })
+        ));
+        assert_eq!(mgr.accounts[&1].available(), 100);
+        assert_eq!(mgr.accounts[&1].withdrawal_count(), 0);
+        assert_eq!(mgr.accounts[&2].available(), u64::MAX - 10);
+        assert_eq!(mgr.is_consistent(), Ok(()));
+    }
+
+    #[test]
+    fn apply_fee() {
+        let mut mgr = Manager::new();
+        mgr.deposit(1, 1, 100, None).expect("Failed to deposit");
+        mgr.apply_fee(1, 2, 30, false).expect("Failed to apply fee");
+
+        assert_eq!(mgr.accounts[&1].available(), 70);
+        assert!(matches!(
+            mgr.apply_fee(1, 3, 80, false),
+            Err(TransactorError::InsufficientFundsForFee {
+                available: 70,
+                attempted: 80
+            })
+        ));
+        assert!(matches!(
+            mgr.apply_fee(2, 4, 10, false),
+            Err(TransactorError::NoClient(2))
+        ));
+        assert_eq!(mgr.accounts[&1].available(), 70);
+    }
+
+    #[test]
+    fn apply_fee_frozen() {
+        let mut mgr = Manager::new();
+        mgr.deposit(1, 1, 100, None).expect("Failed to deposit");
+        mgr.deposit(1, 2, 50, None).expect("Failed to deposit");
+        mgr.dispute(1, 2).expect("Failed to dispute");
+        mgr.chargeback(1, 2).expect("Failed to chargeback");
+
+        assert!(matches!(
+            mgr.apply_fee(1, 3, 10, false),
+            Err(TransactorError::FrozenAccount)
+        ));
+        assert_eq!(mgr.accounts[&1].available(), 100);
+
+        mgr.apply_fee(1, 3, 10, true).expect("Failed to apply fee");
+        assert_eq!(mgr.accounts[&1].available(), 90);
+        assert!(mgr.accounts[&1].is_frozen());
     }
 
     #[test]
     fn dispute_resolve() {
         let mut mgr = Manager::new();
-        mgr.deposit(1, 1, 100).expect("Failed to deposit");
-        mgr.deposit(1, 3, 100).expect("Failed to deposit");
-        mgr.deposit(2, 2, 200).expect("Failed to deposit");
+        mgr.deposit(1, 1, 100, None).expect("Failed to deposit");
+        mgr.deposit(1, 3, 100, None).expect("Failed to deposit");
+        mgr.deposit(2, 2, 200, None).expect("Failed to deposit");
         mgr.dispute(1, 3).expect("Failed to dispute");
 
         validate_accounts(&mgr, &[1, 2]);
@@ -182,9 +2213,9 @@ mod tests {
     fn dispute_chargeback() {
         let mut mgr = Manager::new();
 
-        mgr.deposit(1, 1, 100).expect("Failed to deposit");
-        mgr.deposit(1, 3, 100).expect("Failed to deposit");
-        mgr.deposit(2, 2, 200).expect("Failed to deposit");
+        mgr.deposit(1, 1, 100, None).expect("Failed to deposit");
+        mgr.deposit(1, 3, 100, None).expect("Failed to deposit");
+        mgr.deposit(2, 2, 200, None).expect("Failed to deposit");
         mgr.dispute(1, 3).expect("Failed to dispute");
 
         validate_accounts(&mgr, &[1, 2]);
@@ -210,7 +2241,450 @@ mod tests {
         assert!(mgr.accounts[&1].is_frozen());
 
         // Atttempt to interact with client 1
-        let result = mgr.withdraw(1, 50);
+        let result = mgr.withdraw(1, 4, 50);
         assert!(matches!(result, Err(TransactorError::FrozenAccount)))
     }
+
+    #[test]
+    fn dispute_other_client_txn() {
+        let mut mgr = Manager::new();
+        mgr.deposit(1, 42, 100, None).expect("Failed to deposit");
+        mgr.deposit(2, 7, 50, None).expect("Failed to deposit");
+
+        // Transaction IDs are only looked up among the deposits of the requesting client
+        assert!(matches!(
+            mgr.dispute(2, 42),
+            Err(TransactorError::NoTransaction(42))
+        ));
+        assert_eq!(mgr.accounts[&1].held(), 0);
+
+        mgr.dispute(1, 42).expect("Failed to dispute");
+        assert!(matches!(
+            mgr.resolve(2, 42),
+            Err(TransactorError::NoTransaction(42))
+        ));
+        assert!(matches!(
+            mgr.chargeback(2, 42),
+            Err(TransactorError::NoTransaction(42))
+        ));
+
+        assert_eq!(mgr.accounts[&1].held(), 100);
+        assert!(!mgr.accounts[&1].is_frozen());
+        assert_eq!(mgr.accounts[&2].available(), 50);
+        assert!(!mgr.accounts[&2].is_frozen());
+    }
+
+    #[test]
+    fn export_client_history() {
+        let mut mgr = Manager::new();
+        mgr.deposit(1, 2, 20000, None).expect("Failed to deposit");
+        mgr.deposit(1, 1, 10000, None).expect("Failed to deposit");
+        mgr.deposit(2, 3, 30000, None).expect("Failed to deposit");
+        mgr.dispute(1, 2).expect("Failed to dispute");
+
+        let mut buf = Vec::new();
+        mgr.export_client_history(1, &mut buf)
+            .expect("Failed to export");
+        assert_eq!(
+            std::str::from_utf8(&buf).expect("Failed to convert"),
+            "tx,amount,disputed,chargebacked\n\
+             1,1.0000,false,false\n\
+             2,2.0000,true,false\n"
+        );
+        assert!(matches!(
+            mgr.export_client_history(3, Vec::new()),
+            Err(TransactorError::NoClient(3))
+        ));
+    }
+
+    #[test]
+    fn export_audit_csv() {
+        let config = Config {
+            audit_log: Some(PathBuf::from("audit.csv")),
+            ..Config::default()
+        };
+        let mut mgr = Manager::from_config(&config);
+
+        let outcome = mgr.deposit(1, 1, 15000, None);
+        mgr.record_audit(AuditEntry {
+            row: 1,
+            operation: Operation::Deposit,
+            client: 1,
+            tx: Some(1),
+            amount: Some(15000),
+            outcome,
+        });
+        let outcome = mgr.dispute(1, 2);
+        mgr.record_audit(AuditEntry {
+            row: 2,
+            operation: Operation::Dispute,
+            client: 1,
+            tx: Some(2),
+            amount: None,
+            outcome,
+        });
+
+        let mut buf = Vec::new();
+        mgr.export_audit_csv(&mut buf, 4).expect("Failed to export");
+        assert_eq!(
+            std::str::from_utf8(&buf).expect("Failed to convert"),
+            "row,type,client,tx,amount,outcome\n\
+             1,deposit,1,1,1.5000,ok\n\
+             2,dispute,1,2,,transaction with id 2 does not exist\n"
+        );
+    }
+
+    #[test]
+    fn audit_disabled() {
+        let mut mgr = Manager::new();
+        mgr.record_audit(AuditEntry {
+            row: 1,
+            operation: Operation::Withdrawal,
+            client: 1,
+            tx: Some(1),
+            amount: Some(100),
+            outcome: Err(TransactorError::NoClient(1)),
+        });
+
+        assert!(!mgr.is_auditing());
+        assert!(mgr.audit_trail().is_empty());
+    }
+
+    #[test]
+    fn from_csv_to_csv() {
+        let config = Config {
+            sort_output: true,
+            ..Config::default()
+        };
+        let input = "type,client,tx,amount\n\
+                     deposit,2,1,5.0\n\
+                     deposit,1,2,2.5\n\
+                     withdrawal,1,3,1.0\n\
+                     withdrawal,2,4,9.0\n";
+
+        let (mgr, result) = Manager::from_csv(input.as_bytes(), &config).expect("Failed to load");
+        assert_eq!(result.processed, 4);
+        assert_eq!(result.skipped, 1);
+        assert_eq!(mgr.account_count(), 2);
+
+        let mut buf = Vec::new();
+        mgr.to_csv(&mut buf, &config).expect("Failed to write");
+        assert_eq!(
+            std::str::from_utf8(&buf).expect("Failed to convert"),
+            "client,available,held,total,locked\n\
+             1,1.5000,0.0000,1.5000,false\n\
+             2,5.0000,0.0000,5.0000,false\n"
+        );
+
+        // The written accounts are not valid transaction records
+        assert!(Manager::from_csv(buf.as_slice(), &config).is_err());
+    }
+
+    #[test]
+    fn save_load() {
+        let mut mgr = Manager::new();
+        mgr.deposit(1, 1, 100, None).expect("Failed to deposit");
+        mgr.deposit(1, 3, 100, None).expect("Failed to deposit");
+        mgr.deposit(2, 2, 200, None).expect("Failed to deposit");
+        mgr.deposit(3, 4, 300, None).expect("Failed to deposit");
+        mgr.dispute(1, 3).expect("Failed to dispute");
+        mgr.dispute(3, 4).expect("Failed to dispute");
+        mgr.chargeback(3, 4).expect("Failed to chargeback");
+
+        let mut buf = Vec::new();
+        mgr.save(&mut buf).expect("Failed to save");
+        let restored = Manager::load(buf.as_slice()).expect("Failed to load");
+
+        assert_eq!(restored.accounts, mgr.accounts);
+        assert_eq!(restored.accounts[&1].held(), 100);
+        assert!(restored.accounts[&3].is_frozen());
+    }
+
+    fn merge_managers() -> (Manager, Manager) {
+        let mut mgr = Manager::new();
+        mgr.deposit(1, 1, 100, None).expect("Failed to deposit");
+        mgr.deposit(2, 2, 200, None).expect("Failed to deposit");
+
+        let mut other = Manager::new();
+        other.deposit(2, 3, 300, None).expect("Failed to deposit");
+        other.deposit(3, 4, 400, None).expect("Failed to deposit");
+
+        (mgr, other)
+    }
+
+    #[test]
+    fn merge_skip_conflicts() {
+        let (mut mgr, other) = merge_managers();
+        mgr.merge(other, MergeStrategy::SkipConflicts)
+            .expect("Failed to merge");
+
+        validate_accounts(&mgr, &[1, 2, 3]);
+        assert_eq!(mgr.accounts[&2].available(), 200);
+        assert_eq!(mgr.accounts[&3].available(), 400);
+    }
+
+    #[test]
+    fn merge_overwrite() {
+        let (mut mgr, other) = merge_managers();
+        mgr.merge(other, MergeStrategy::OverwriteWithOther)
+            .expect("Failed to merge");
+
+        validate_accounts(&mgr, &[1, 2, 3]);
+        assert_eq!(mgr.accounts[&2].available(), 300);
+        assert_eq!(mgr.accounts[&3].available(), 400);
+    }
+
+    #[test]
+    fn merge_error_on_conflict() {
+        let (mut mgr, other) = merge_managers();
+        let result = mgr.merge(other, MergeStrategy::ErrorOnConflict);
+
+        assert!(matches!(
+            result,
+            Err(TransactorError::ClientAlreadyExists(2))
+        ));
+        validate_accounts(&mgr, &[1, 2]);
+        assert_eq!(mgr.accounts[&2].available(), 200);
+    }
+
+    #[test]
+    fn from_iter() {
+        let accounts = vec![
+            (1, Account::new(1, 100).unwrap()),
+            (2, Account::new(2, 200).unwrap()),
+            (1, Account::new(3, 300).unwrap()),
+        ];
+        let mgr: Manager = accounts.into_iter().collect();
+
+        validate_accounts(&mgr, &[1, 2]);
+        assert_eq!(mgr.accounts[&1].available(), 100);
+        assert_eq!(mgr.accounts[&2].available(), 200);
+    }
+
+    #[test]
+    fn extend() {
+        let mut mgr = Manager::new();
+        mgr.deposit(1, 1, 100, None).expect("Failed to deposit");
+        mgr.extend(vec![
+            (1, Account::new(2, 200).unwrap()),
+            (3, Account::new(3, 300).unwrap()),
+        ]);
+
+        validate_accounts(&mgr, &[1, 3]);
+        assert_eq!(mgr.accounts[&1].available(), 100);
+        assert_eq!(mgr.accounts[&3].available(), 300);
+    }
+
+    #[test]
+    fn retain() {
+        let mut mgr = Manager::new();
+        mgr.deposit(1, 1, 100, None).expect("Failed to deposit");
+        mgr.deposit(2, 2, 200, None).expect("Failed to deposit");
+        mgr.deposit(3, 3, 300, None).expect("Failed to deposit");
+        mgr.retain(|client, acct| client != 2 && acct.available() > 100)
+            .expect("Failed to retain");
+
+        validate_accounts(&mgr, &[3]);
+    }
+
+    #[test]
+    fn is_consistent() {
+        let mut mgr = Manager::new();
+        for client in 1..=3 {
+            mgr.deposit(client, u32::from(client), 10000, None)
+                .expect("Failed to deposit");
+        }
+        mgr.dispute(2, 2).expect("Failed to dispute");
+        assert_eq!(mgr.is_consistent(), Ok(()));
+
+        // Corrupt the held funds of clients 1 and 2
+        let mut state: serde_json::Value = serde_json::to_value(&mgr).expect("Failed to serialize");
+        state["accounts"]["1"]["held"] = "0.5000".into();
+        state["accounts"]["2"]["held"] = "0.0001".into();
+        let corrupted: Manager = serde_json::from_value(state).expect("Failed to deserialize");
+
+        assert_eq!(
+            corrupted.is_consistent(),
+            Err(vec![
+                (
+                    1,
+                    "held funds of 5000 do not match disputed deposits of 0".to_string()
+                ),
+                (
+                    2,
+                    "held funds of 1 do not match disputed deposits of 10000".to_string()
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn set_client_frozen() {
+        let mut mgr = Manager::new();
+        mgr.deposit(1, 1, 100, None).expect("Failed to deposit");
+        mgr.deposit(1, 2, 50, None).expect("Failed to deposit");
+        mgr.dispute(1, 1).expect("Failed to dispute");
+        mgr.chargeback(1, 1).expect("Failed to chargeback");
+        assert!(matches!(
+            mgr.deposit(1, 3, 10, None),
+            Err(TransactorError::FrozenAccount)
+        ));
+
+        // An unfrozen account accepts new deposits
+        mgr.set_client_frozen(1, false).expect("Failed to unfreeze");
+        mgr.deposit(1, 3, 10, None).expect("Failed to deposit");
+        assert_eq!(mgr.accounts[&1].available(), 60);
+        assert_eq!(mgr.is_consistent(), Ok(()));
+
+        mgr.set_client_frozen(1, true).expect("Failed to freeze");
+        assert!(mgr.accounts[&1].is_frozen());
+        assert!(matches!(
+            mgr.withdraw(1, 4, 10),
+            Err(TransactorError::FrozenAccount)
+        ));
+
+        assert!(matches!(
+            mgr.set_client_frozen(2, true),
+            Err(TransactorError::NoClient(2))
+        ));
+    }
+
+    #[test]
+    fn unfreeze_chargebacked_deposit() {
+        let mut mgr = Manager::new();
+        mgr.deposit(1, 1, 100, None).expect("Failed to deposit");
+        mgr.deposit(1, 2, 100, None).expect("Failed to deposit");
+        mgr.dispute(1, 1).expect("Failed to dispute");
+        mgr.chargeback(1, 1).expect("Failed to chargeback");
+        mgr.set_client_frozen(1, false).expect("Failed to unfreeze");
+
+        // A charged back deposit cannot be disputed, and charged back, a second time
+        assert!(!mgr.accounts[&1].can_dispute(1));
+        assert!(matches!(
+            mgr.dispute(1, 1),
+            Err(TransactorError::ChargebackedTxn(1))
+        ));
+        assert!(matches!(
+            mgr.chargeback(1, 1),
+            Err(TransactorError::NonDisputedTxn(1))
+        ));
+
+        let acct = &mgr.accounts[&1];
+        assert_eq!(acct.available(), 100);
+        assert_eq!(acct.held(), 0);
+        assert!(!acct.is_frozen());
+        assert_eq!(mgr.is_consistent(), Ok(()));
+    }
+
+    #[test]
+    fn tx_id_space_exhausted() {
+        let mut mgr = Manager::new();
+        assert_eq!(mgr.next_available_tx_id(), Some(1));
+
+        mgr.deposit(1, 5, 100, None).expect("Failed to deposit");
+        mgr.deposit(2, 3, 100, None).expect("Failed to deposit");
+        assert_eq!(mgr.next_available_tx_id(), Some(6));
+
+        mgr.deposit(1, u32::MAX - 1, 100, None)
+            .expect("Failed to deposit");
+        assert_eq!(mgr.next_available_tx_id(), Some(u32::MAX));
+
+        assert!(matches!(
+            mgr.deposit(1, u32::MAX, 100, None),
+            Err(TransactorError::TxIdSpaceExhausted)
+        ));
+        assert!(matches!(
+            mgr.transfer(1, 2, u32::MAX, 100),
+            Err(TransactorError::TransferFailed { .. })
+        ));
+        assert_eq!(mgr.accounts[&1].available(), 200);
+        assert_eq!(mgr.next_available_tx_id(), Some(u32::MAX));
+    }
+
+    #[test]
+    fn next_available_tx_id_not_reused() {
+        let mut mgr = Manager::new();
+        mgr.deposit(1, 1, 100, None).expect("Failed to deposit");
+        mgr.deposit(1, 2, 100, None).expect("Failed to deposit");
+        mgr.dispute(1, 2).expect("Failed to dispute");
+        mgr.chargeback(1, 2).expect("Failed to chargeback");
+
+        // The charged back deposit is no longer tracked, but its ID is still used
+        let (_, acct) = mgr.accounts_iter_mut().next().expect("Missing account");
+        acct.remove_chargeback_deposit(2)
+            .expect("Failed to remove chargeback");
+        assert_eq!(mgr.next_available_tx_id(), Some(3));
+
+        // Fees are not tracked by the account, and removed accounts take their transactions with them
+        mgr.apply_fee(1, 3, 10, true).expect("Failed to apply fee");
+        assert_eq!(mgr.next_available_tx_id(), Some(4));
+        mgr.deposit(2, 4, 100, None).expect("Failed to deposit");
+        mgr.withdraw(2, 5, 100).expect("Failed to withdraw");
+        assert_eq!(mgr.remove_empty_accounts().expect("Failed to remove"), 1);
+        assert_eq!(mgr.next_available_tx_id(), Some(6));
+
+        // Refused operations do not use their ID
+        assert!(mgr.withdraw(1, 9, 1000).is_err());
+        assert_eq!(mgr.next_available_tx_id(), Some(6));
+    }
+
+    #[test]
+    fn remove_empty_accounts() {
+        let mut mgr = Manager::new();
+        mgr.register_client(1).expect("Failed to register");
+        mgr.deposit(2, 2, 200, None).expect("Failed to deposit");
+        mgr.withdraw(2, 4, 200).expect("Failed to withdraw");
+        mgr.deposit(3, 3, 300, None).expect("Failed to deposit");
+
+        // A frozen account with no funds is kept
+        mgr.deposit(4, 4, 400, None).expect("Failed to deposit");
+        mgr.dispute(4, 4).expect("Failed to dispute");
+        mgr.chargeback(4, 4).expect("Failed to chargeback");
+        assert_eq!(mgr.accounts[&4].total(), 0);
+
+        // An account with only held funds is kept
+        mgr.deposit(5, 5, 500, None).expect("Failed to deposit");
+        mgr.dispute(5, 5).expect("Failed to dispute");
+
+        assert_eq!(mgr.remove_empty_accounts().expect("Failed to remove"), 2);
+        validate_accounts(&mgr, &[3, 4, 5]);
+        assert_eq!(mgr.remove_empty_accounts().expect("Failed to remove"), 0);
+    }
+
+    fn not_closeable(mgr: &mut Manager, client: u16) -> String {
+        match mgr.close_account(client) {
+            Err(TransactorError::AccountNotCloseable { reason }) => reason,
+            result => panic!("Expected AccountNotCloseable, got {result:?}"),
+        }
+    }
+
+    #[test]
+    fn close_account() {
+        let mut mgr = Manager::new();
+
+        // Nonzero balance
+        mgr.deposit(1, 1, 15000, None).expect("Failed to deposit");
+        assert_eq!(not_closeable(&mut mgr, 1), "account holds funds of 1.5000");
+
+        // Active dispute
+        mgr.deposit(2, 2, 200, None).expect("Failed to deposit");
+        mgr.dispute(2, 2).expect("Failed to dispute");
+        assert_eq!(not_closeable(&mut mgr, 2), "account has disputed deposits");
+
+        // Frozen, with no funds
+        mgr.deposit(3, 3, 300, None).expect("Failed to deposit");
+        mgr.dispute(3, 3).expect("Failed to dispute");
+        mgr.chargeback(3, 3).expect("Failed to chargeback");
+        assert_eq!(not_closeable(&mut mgr, 3), "account is frozen");
+
+        // Closeable once the funds are withdrawn
+        mgr.withdraw(1, 4, 15000).expect("Failed to withdraw");
+        mgr.close_account(1).expect("Failed to close");
+        validate_accounts(&mgr, &[2, 3]);
+
+        assert!(matches!(
+            mgr.close_account(1),
+            Err(TransactorError::NoClient(1))
+        ));
+    }
 }
@@ -0,0 +1,42 @@
+use crate::operation::Operation;
+use serde::Serialize;
+
+/// Counts of successful and refused operations, by operation type.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct Metrics {
+    pub deposits_ok: u64,
+    pub deposits_err: u64,
+    pub withdrawals_ok: u64,
+    pub withdrawals_err: u64,
+    pub disputes_ok: u64,
+    pub disputes_err: u64,
+    pub resolves_ok: u64,
+    pub resolves_err: u64,
+    pub chargebacks_ok: u64,
+    pub chargebacks_err: u64,
+}
+
+impl Metrics {
+    /// Count the outcome of an operation. Operations without counters are ignored.
+    #[inline]
+    pub fn record(&mut self, operation: Operation, ok: bool) {
+        let (ok_count, err_count) = match operation {
+            Operation::Deposit => (&mut self.deposits_ok, &mut self.deposits_err),
+            Operation::Withdrawal => (&mut self.withdrawals_ok, &mut self.withdrawals_err),
+            Operation::Dispute => (&mut self.disputes_ok, &mut self.disputes_err),
+            Operation::Resolve => (&mut self.resolves_ok, &mut self.resolves_err),
+            Operation::Chargeback => (&mut self.chargebacks_ok, &mut self.chargebacks_err),
+            Operation::Transfer
+            | Operation::Fee
+            | Operation::Register
+            | Operation::AdminUnfreeze
+            | Operation::AdminFreeze => return,
+        };
+
+        if ok {
+            *ok_count += 1;
+        } else {
+            *err_count += 1;
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use crate::error::TransactorError;
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+/// The set of valid account operations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Operation {
+    Withdrawal,
+    Deposit,
+    Dispute,
+    Resolve,
+    Chargeback,
+    Transfer,
+    Fee,
+    Register,
+    #[serde(rename = "admin_unfreeze")]
+    AdminUnfreeze,
+    #[serde(rename = "admin_freeze")]
+    AdminFreeze,
+}
+
+impl Operation {
+    /// Every operation, in declaration order.
+    pub const ALL: [Operation; 10] = [
+        Operation::Withdrawal,
+        Operation::Deposit,
+        Operation::Dispute,
+        Operation::Resolve,
+        Operation::Chargeback,
+        Operation::Transfer,
+        Operation::Fee,
+        Operation::Register,
+        Operation::AdminUnfreeze,
+        Operation::AdminFreeze,
+    ];
+
+    /// Get the lowercase name of the operation, as it appears in the CSV type column.
+    #[inline]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Operation::Withdrawal => "withdrawal",
+            Operation::Deposit => "deposit",
+            Operation::Dispute => "dispute",
+            Operation::Resolve => "resolve",
+            Operation::Chargeback => "chargeback",
+            Operation::Transfer => "transfer",
+            Operation::Fee => "fee",
+            Operation::Register => "register",
+            Operation::AdminUnfreeze => "admin_unfreeze",
+            Operation::AdminFreeze => "admin_freeze",
+        }
+    }
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Operation {
+    type Err = TransactorError;
+
+    /// Parse an operation name, ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "withdrawal" => Ok(Operation::Withdrawal),
+            "deposit" => Ok(Operation::Deposit),
+            "dispute" => Ok(Operation::Dispute),
+            "resolve" => Ok(Operation::Resolve),
+            "chargeback" => Ok(Operation::Chargeback),
+            "transfer" => Ok(Operation::Transfer),
+            "fee" => Ok(Operation::Fee),
+            "register" => Ok(Operation::Register),
+            "admin_unfreeze" => Ok(Operation::AdminUnfreeze),
+            "admin_freeze" => Ok(Operation::AdminFreeze),
+            _ => Err(TransactorError::UnknownOperation(s.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Operation;
+    use crate::error::TransactorError;
+
+    const OPERATIONS: [(Operation, &str); 10] = [
+        (Operation::Withdrawal, "withdrawal"),
+        (Operation::Deposit, "deposit"),
+        (Operation::Dispute, "dispute"),
+        (Operation::Resolve, "resolve"),
+        (Operation::Chargeback, "chargeback"),
+        (Operation::Transfer, "transfer"),
+        (Operation::Fee, "fee"),
+        (Operation::Register, "register"),
+        (Operation::AdminUnfreeze, "admin_unfreeze"),
+        (Operation::AdminFreeze, "admin_freeze"),
+    ];
+
+    #[test]
+    fn display() {
+        for (operation, name) in OPERATIONS {
+            assert_eq!(operation.to_string(), name);
+        }
+    }
+
+    #[test]
+    fn from_str() {
+        for (operation, name) in OPERATIONS {
+            assert_eq!(name.parse::<Operation>().unwrap(), operation);
+            assert_eq!(name.to_uppercase().parse::<Operation>().unwrap(), operation);
+        }
+
+        assert_eq!(
+            "ChargeBack".parse::<Operation>().unwrap(),
+            Operation::Chargeback
+        );
+    }
+
+    #[test]
+    fn from_str_unknown() {
+        for name in ["", "refund", "deposits", " deposit"] {
+            let result = name.parse::<Operation>();
+            assert!(matches!(result, Err(TransactorError::UnknownOperation(ref s)) if s == name));
+        }
+    }
+}
@@ -1,5 +1,17 @@
-mod deserialze;
-mod serialize;
+//! Reading transaction records from CSV and writing account state, in CSV, JSON, or snapshot form.
 
-pub use deserialze::load_data;
-pub use serialize::unload_data;
+pub mod reader;
+mod snapshot;
+pub mod writer;
+
+#[cfg(feature = "async")]
+pub use reader::load_data_async;
+pub use reader::{
+    apply_all, load, load_ndjson, read_all, validate_data, ProcessResult, TransactionRecord,
+    TransactionRecordBuilder,
+};
+pub use snapshot::{read_snapshot, write_snapshot};
+pub use writer::{
+    dump, dump_ref, write_accounts_filtered, write_as_json_array, write_as_json_object,
+    write_audit_log, write_metrics, write_stats,
+};
@@ -0,0 +1,1408 @@
+use crate::{
+    audit::AuditEntry,
+    config::{Config, Verbosity},
+    error::TransactorError,
+    fixed_point::{FixedPoint, DECIMALS},
+    manager::Manager,
+    operation::Operation,
+};
+use serde::{Deserialize, Deserializer};
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, BufReader, Read},
+};
+#[cfg(feature = "async")]
+use tokio::io::{AsyncBufReadExt, AsyncRead};
+use tracing::Level;
+
+/// The representation of a CSV transaction record.
+/// Fields are matched by header name, so columns may appear in any order.
+/// The `amount` column may be omitted entirely, as in dispute-only files.
+/// The `target` column is only required by transfer operations,
+/// and the `tx` field may only be left empty by register operations.
+/// The optional `description` column tags deposits with a reference string.
+#[derive(Debug, Deserialize)]
+pub struct TransactionRecord {
+    #[serde(rename = "type", deserialize_with = "operation_from_str")]
+    operation: Operation,
+    #[serde(rename = "client")]
+    client: u16,
+    #[serde(rename = "tx")]
+    tx: Option<u32>,
+    #[serde(rename = "amount", default, deserialize_with = "quantity_from_str")]
+    amount: Option<u64>,
+    #[serde(rename = "target", default)]
+    target_client: Option<u16>,
+    #[serde(rename = "description", default)]
+    description: Option<String>,
+}
+
+impl TransactionRecord {
+    /// Check the record is well formed before it is applied to any `Manager`:
+    /// deposits and withdrawals must have a non-zero amount, disputes, resolves, and chargebacks
+    /// must have no amount, and neither the client ID nor any transaction ID may be zero.
+    #[inline]
+    pub fn validate(&self) -> Result<(), TransactorError> {
+        if self.client == 0 {
+            return Err(TransactorError::ZeroClient);
+        }
+
+        if self.tx == Some(0) {
+            return Err(TransactorError::ZeroTxn);
+        }
+
+        match (self.operation, self.amount) {
+            (Operation::Deposit | Operation::Withdrawal, None) => {
+                Err(TransactorError::MissingAmount)
+            }
+            (Operation::Deposit | Operation::Withdrawal, Some(0)) => {
+                Err(TransactorError::ZeroAmount)
+            }
+            (Operation::Dispute | Operation::Resolve | Operation::Chargeback, Some(_)) => {
+                Err(TransactorError::UnexpectedAmount)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Consumes the `TransactionRecord` and applies it to the `Manager` according to the `Config`,
+    /// returning any error encountered.
+    #[inline]
+    fn process(self, manager: &mut Manager, config: &Config) -> Result<(), TransactorError> {
+        self.validate()?;
+
+        match self.operation {
+            Operation::Register => return manager.register_client(self.client),
+            Operation::AdminUnfreeze => return manager.set_client_frozen(self.client, false),
+            Operation::AdminFreeze => return manager.set_client_frozen(self.client, true),
+            _ => {}
+        }
+
+        let tx = self.tx.ok_or(TransactorError::MissingTxn)?;
+
+        match self.operation {
+            Operation::Transfer => {
+                let amt = self.amount.ok_or(TransactorError::MissingAmount)?;
+                let target = self.target_client.ok_or(TransactorError::MissingTarget)?;
+                manager.transfer(self.client, target, tx, amt)
+            }
+            Operation::Fee => {
+                let amt = self.amount.ok_or(TransactorError::MissingAmount)?;
+                manager.apply_fee(self.client, tx, amt, config.fee_on_frozen)
+            }
+            Operation::Deposit => {
+                let amt = self.amount.ok_or(TransactorError::MissingAmount)?;
+                manager.deposit(self.client, tx, amt, self.description)
+            }
+            op => manager.apply_record(op, self.client, tx, self.amount),
+        }
+    }
+
+    /// Parse the amount again from its raw text at the configured precision,
+    /// when it differs from the ten thousandths place the amount was deserialized at.
+    #[inline]
+    fn rescale(&mut self, amount: Option<&str>, config: &Config) -> Result<(), TransactorError> {
+        let precision = config.precision();
+        if precision == DECIMALS {
+            return Ok(());
+        }
+
+        self.amount = amount
+            .filter(|amount| !amount.is_empty())
+            .map(|amount| FixedPoint::from_str_with_precision(amount, precision))
+            .transpose()?
+            .map(FixedPoint::raw);
+        Ok(())
+    }
+}
+
+/// A builder of `TransactionRecord`s, for constructing records without a CSV source.
+/// Every field other than the operation and client is absent unless set.
+/// Operations that require an amount, transaction ID, or target are refused when processed without one.
+#[derive(Debug, Clone)]
+pub struct TransactionRecordBuilder {
+    operation: Operation,
+    client: u16,
+    tx: Option<u32>,
+    amount: Option<u64>,
+    target_client: Option<u16>,
+    description: Option<String>,
+}
+
+impl TransactionRecordBuilder {
+    /// Construct a new `TransactionRecordBuilder` for the operation on client 0.
+    #[inline]
+    pub fn new(operation: Operation) -> Self {
+        TransactionRecordBuilder {
+            operation,
+            client: 0,
+            tx: None,
+            amount: None,
+            target_client: None,
+            description: None,
+        }
+    }
+
+    /// Set the operation.
+    #[inline]
+    pub fn operation(mut self, operation: Operation) -> Self {
+        self.operation = operation;
+        self
+    }
+
+    /// Set the client ID.
+    #[inline]
+    pub fn client(mut self, client: u16) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Set the transaction ID.
+    #[inline]
+    pub fn tx(mut self, tx: u32) -> Self {
+        self.tx = Some(tx);
+        self
+    }
+
+    /// Set the amount from a decimal, truncated to the ten thousandths place as if read from CSV.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the amount is negative, not finite, or too large to represent.
+    #[inline]
+    pub fn amount(self, amt: f64) -> Self {
+        let amount = FixedPoint::from_str_truncated(&amt.to_string())
+            .unwrap_or_else(|error| panic!("Invalid amount {amt}: {error}"));
+        self.amount_raw(amount.raw())
+    }
+
+    /// Set the amount from a u64 already scaled to the ten thousandths place.
+    #[inline]
+    pub fn amount_raw(mut self, scaled: u64) -> Self {
+        self.amount = Some(scaled);
+        self
+    }
+
+    /// Set the target client of a transfer.
+    #[inline]
+    pub fn target(mut self, client: u16) -> Self {
+        self.target_client = Some(client);
+        self
+    }
+
+    /// Set the description of a deposit.
+    #[inline]
+    pub fn description(mut self, description: &str) -> Self {
+        self.description = Some(description.to_string());
+        self
+    }
+
+    /// Construct the `TransactionRecord`.
+    #[inline]
+    pub fn build(self) -> TransactionRecord {
+        TransactionRecord {
+            operation: self.operation,
+            client: self.client,
+            tx: self.tx,
+            amount: self.amount,
+            target_client: self.target_client,
+            description: self.description,
+        }
+    }
+}
+
+/// The outcome of loading a set of transaction records.
+#[derive(Debug, Default)]
+pub struct ProcessResult {
+    /// The number of records read and applied to the `Manager`.
+    pub processed: usize,
+
+    /// The number of records whose operation was refused due to a soft error.
+    pub skipped: usize,
+
+    /// The soft errors encountered, paired with the row of the offending record.
+    pub soft_errors: Vec<(u64, TransactorError)>,
+
+    /// The number of records read of each operation type, whether or not they were applied.
+    pub operations: HashMap<Operation, usize>,
+}
+
+/// Deserialize an operation name, ignoring case.
+#[inline]
+fn operation_from_str<'de, D>(d: D) -> Result<Operation, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value: &str = Deserialize::deserialize(d)?;
+    value.parse().map_err(serde::de::Error::custom)
+}
+
+/// Deserialize a decimal string into a u64 scaled to the ten thousandths place.
+/// Digits beyond the ten thousandths place are truncated.
+#[inline]
+fn quantity_from_str<'de, D>(d: D) -> Result<Option<u64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value: Option<&str> = Deserialize::deserialize(d)?;
+
+    value
+        .map(|amount| FixedPoint::from_str_truncated(amount).map(FixedPoint::raw))
+        .transpose()
+        .map_err(serde::de::Error::custom)
+}
+
+/// The positional column mapping used when the input has no header row.
+const COLUMNS: [&str; 6] = ["type", "client", "tx", "amount", "target", "description"];
+
+/// Construct a ParseErrorAt error with the row adjusted by the offset,
+/// accounting for the absence of a header row.
+#[inline]
+fn parse_error_at(error: csv::Error, offset: u64) -> TransactorError {
+    match TransactorError::parse_error(error) {
+        TransactorError::ParseErrorAt { row, source } => TransactorError::ParseErrorAt {
+            row: row + offset,
+            source,
+        },
+        error => error,
+    }
+}
+
+/// Construct a CSV reader over the input using the configured delimiter.
+/// Leading and trailing whitespace is trimmed from every field, including headers.
+/// A UTF-8 byte order mark at the start of the input is skipped by the csv reader itself.
+/// Headerless input may omit the trailing `amount`, `target`, and `description` columns, so record lengths may vary.
+#[inline]
+fn csv_reader<R: Read>(reader: R, config: &Config) -> csv::Reader<R> {
+    csv::ReaderBuilder::new()
+        .delimiter(config.delimiter)
+        .has_headers(!config.no_header)
+        .flexible(config.no_header)
+        .trim(csv::Trim::All)
+        .from_reader(reader)
+}
+
+/// Find the position of the `amount` column, if present.
+#[inline]
+fn amount_column(headers: &csv::StringRecord) -> Option<usize> {
+    headers.iter().position(|header| header == "amount")
+}
+
+/// Read and discard the first lines of the input, each terminated by `\n`.
+#[inline]
+fn skip_lines<R: BufRead>(reader: &mut R, lines: usize) -> Result<(), io::Error> {
+    let mut line = Vec::new();
+
+    for _ in 0..lines {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Read the header row, or use the positional column mapping when the input has no header row.
+#[inline]
+fn read_headers<R: Read>(
+    rdr: &mut csv::Reader<R>,
+    config: &Config,
+) -> Result<csv::StringRecord, TransactorError> {
+    if config.no_header {
+        return Ok(csv::StringRecord::from(COLUMNS.to_vec()));
+    }
+
+    Ok(rdr.headers().map_err(TransactorError::parse_error)?.clone())
+}
+
+/// Deserialize data from the reader, after skipping the configured number of preamble lines,
+/// and apply each record to a scratch `Manager`, collecting every error paired with its row rather than halting.
+/// Operations are counted against the configured limit per client, as when loading.
+/// Parse errors and errors that are normally fatal are collected as well; only IO failures halt validation.
+#[inline]
+pub fn validate_data<R: Read>(
+    reader: R,
+    config: &Config,
+) -> Result<Vec<(u64, TransactorError)>, TransactorError> {
+    let mut reader = BufReader::new(reader);
+    skip_lines(&mut reader, config.skip_lines)?;
+    let mut rdr = csv_reader(reader, config);
+    let headers = read_headers(&mut rdr, config)?;
+    let column = amount_column(&headers);
+    let offset = u64::from(config.no_header);
+    let mut manager = Manager::from_config(config);
+    let mut errors = Vec::new();
+
+    for raw in rdr.records() {
+        let parsed = raw.and_then(|raw| {
+            let row = raw.position().map_or(0, csv::Position::record) + offset;
+            let record: TransactionRecord = raw.deserialize(Some(&headers))?;
+            Ok((row, record, raw))
+        });
+
+        match parsed {
+            Ok((row, mut record, raw)) => {
+                let outcome = record
+                    .rescale(column.and_then(|i| raw.get(i)), config)
+                    .and_then(|()| manager.count_client_op(record.client))
+                    .and_then(|()| record.process(&mut manager, config));
+
+                if let Err(error) = outcome {
+                    errors.push((row, error));
+                }
+            }
+            Err(error) if error.is_io_error() => return Err(error.into()),
+            Err(error) => match parse_error_at(error, offset) {
+                error @ TransactorError::ParseErrorAt { row, .. } => errors.push((row, error)),
+                error => errors.push((0, error)),
+            },
+        }
+    }
+
+    Ok(errors)
+}
+
+/// Deserialize data from the reader and apply each record to the `Manager`,
+/// after skipping the configured number of preamble lines.
+/// The outcome of each record is reported to stderr according to the configured verbosity.
+/// In strict mode, the first soft error halts processing.
+#[inline]
+pub fn load<R: Read>(
+    reader: R,
+    manager: &mut Manager,
+    config: &Config,
+) -> Result<ProcessResult, TransactorError> {
+    let mut reader = BufReader::new(reader);
+    skip_lines(&mut reader, config.skip_lines)?;
+    let mut rdr = csv_reader(reader, config);
+    let mut result = ProcessResult::default();
+    load_records(&mut rdr, 0, manager, config, &mut result)?;
+
+    Ok(result)
+}
+
+/// Read the header row from the CSV reader, then deserialize and apply each record to the `Manager`,
+/// numbering rows after the given number of preceding records. Returns the number of records read.
+#[inline]
+fn load_records<R: Read>(
+    rdr: &mut csv::Reader<R>,
+    preceding: u64,
+    manager: &mut Manager,
+    config: &Config,
+    result: &mut ProcessResult,
+) -> Result<u64, TransactorError> {
+    let headers = read_headers(rdr, config)?;
+    let column = amount_column(&headers);
+
+    // Rows are numbered from 1 for the first record, whether or not a header is present.
+    let offset = preceding + u64::from(config.no_header);
+    let mut row = preceding;
+
+    for raw in rdr.records() {
+        let raw = raw.map_err(|error| parse_error_at(error, offset))?;
+        row = raw.position().map_or(0, csv::Position::record) + offset;
+        let mut record: TransactionRecord = raw
+            .deserialize(Some(&headers))
+            .map_err(|error| parse_error_at(error, offset))?;
+        record.rescale(column.and_then(|i| raw.get(i)), config)?;
+        apply(record, row, manager, config, result)?;
+    }
+
+    Ok(row - preceding)
+}
+
+/// Deserialize newline delimited JSON from the reader, one transaction record object per line,
+/// and apply each record to the `Manager` exactly as `load` does.
+/// Fields are named as the CSV columns, and amounts are decimal strings as in the NDJSON output.
+/// Rows are numbered by line from 1, and blank lines are skipped.
+#[inline]
+pub fn load_ndjson<R: Read>(
+    reader: R,
+    manager: &mut Manager,
+    config: &Config,
+) -> Result<ProcessResult, TransactorError> {
+    let mut result = ProcessResult::default();
+
+    for (row, line) in (1..).zip(BufReader::new(reader).lines()) {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let json_error = |error| TransactorError::json_parse_error(row, error);
+        let mut record: TransactionRecord = serde_json::from_str(&line).map_err(json_error)?;
+
+        if config.precision() != DECIMALS {
+            let value: serde_json::Value = serde_json::from_str(&line).map_err(json_error)?;
+            record.rescale(
+                value.get("amount").and_then(serde_json::Value::as_str),
+                config,
+            )?;
+        }
+
+        apply(record, row, manager, config, &mut result)?;
+    }
+
+    Ok(result)
+}
+
+/// Apply the record at the row to the `Manager`, tallying the outcome in the `ProcessResult`
+/// and reporting it to stderr according to the configured verbosity.
+/// Fatal errors, and every error in strict mode, are returned rather than recorded.
+#[inline]
+fn apply(
+    record: TransactionRecord,
+    row: u64,
+    manager: &mut Manager,
+    config: &Config,
+    result: &mut ProcessResult,
+) -> Result<(), TransactorError> {
+    let (operation, client, tx, amount) =
+        (record.operation, record.client, record.tx, record.amount);
+    result.processed += 1;
+    *result.operations.entry(operation).or_default() += 1;
+
+    let outcome = manager
+        .count_client_op(client)
+        .and_then(|()| record.process(manager, config));
+
+    if manager.is_auditing() {
+        manager.record_audit(AuditEntry {
+            row,
+            operation,
+            client,
+            tx,
+            amount,
+            outcome: outcome.clone(),
+        });
+    }
+
+    // Soft errors resulting from manager interaction are recorded and their effects ignored.
+    // Upon encountering a soft error, the parsing process is allowed to continue.
+    // Operations on frozen accounts are still recorded when ignored, but never reported.
+    // Outcomes are only written to stderr directly when no tracing subscriber records the event,
+    // so that each is reported once.
+    match outcome {
+        Ok(()) => {
+            tracing::debug!(row, %operation, client, "operation applied");
+
+            if config.verbosity == Verbosity::Verbose && !tracing::enabled!(Level::DEBUG) {
+                eprintln!("row {row}: {operation} client {client}: ok");
+            }
+        }
+        Err(error) if error.is_fatal() || config.strict => return Err(error),
+        Err(error) => {
+            let silent = config.ignore_frozen && error.is_frozen();
+            if !silent {
+                tracing::warn!(row, %operation, client, %error, "operation refused");
+            }
+
+            if config.verbosity != Verbosity::Quiet && !silent && !tracing::enabled!(Level::WARN) {
+                eprintln!("row {row}: {operation} client {client}: {error}");
+            }
+            result.skipped += 1;
+            result.soft_errors.push((row, error));
+        }
+    }
+
+    Ok(())
+}
+
+/// Deserialize every record from the reader into memory, without applying any to a `Manager`.
+/// The input must have a comma delimited header row; the first parse error halts reading.
+#[inline]
+pub fn read_all<R: Read>(reader: R) -> Result<Vec<TransactionRecord>, TransactorError> {
+    csv_reader(reader, &Config::default())
+        .into_deserialize()
+        .map(|record| record.map_err(TransactorError::parse_error))
+        .collect()
+}
+
+/// Apply each of the records to the `Manager` in order, as `load` does, numbering rows from 1.
+/// The first fatal error, or any error in strict mode, halts processing;
+/// it is recorded among the soft errors and the remaining records are left unapplied.
+#[inline]
+pub fn apply_all(
+    records: Vec<TransactionRecord>,
+    manager: &mut Manager,
+    config: &Config,
+) -> ProcessResult {
+    let mut result = ProcessResult::default();
+
+    for (row, record) in (1..).zip(records) {
+        if let Err(error) = apply(record, row, manager, config, &mut result) {
+            result.skipped += 1;
+            result.soft_errors.push((row, error));
+            break;
+        }
+    }
+
+    result
+}
+
+/// The number of lines read from an asynchronous reader before the records among them are applied.
+#[cfg(feature = "async")]
+const ASYNC_CHUNK_LINES: usize = 1024;
+
+/// Read the data from the asynchronous reader in chunks of lines, applying the records of each chunk
+/// to the `Manager` exactly as `load` does before the next chunk is read, so memory use is bounded by the chunk.
+/// A chunk only ends outside of a quoted field, so fields spanning lines are kept whole.
+#[cfg(feature = "async")]
+#[inline]
+pub async fn load_data_async<R: AsyncRead + Unpin>(
+    reader: R,
+    manager: &mut Manager,
+    config: &Config,
+) -> Result<ProcessResult, TransactorError> {
+    let mut reader = tokio::io::BufReader::new(reader);
+    let mut line = Vec::new();
+
+    for _ in 0..config.skip_lines {
+        line.clear();
+        if reader.read_until(b'\n', &mut line).await? == 0 {
+            break;
+        }
+    }
+
+    // The header row begins every chunk, so that each chunk is read as a complete input.
+    let mut header = Vec::new();
+    if !config.no_header {
+        reader.read_until(b'\n', &mut header).await?;
+    }
+
+    let mut result = ProcessResult::default();
+    let mut chunk = header.clone();
+    let (mut lines, mut quoted, mut rows) = (0, false, 0);
+
+    loop {
+        line.clear();
+        let eof = reader.read_until(b'\n', &mut line).await? == 0;
+        chunk.extend_from_slice(&line);
+        lines += 1;
+        quoted ^= line.iter().filter(|&&byte| byte == b'"').count() % 2 == 1;
+
+        if eof || (lines >= ASYNC_CHUNK_LINES && !quoted) {
+            let mut rdr = csv_reader(chunk.as_slice(), config);
+            rows += load_records(&mut rdr, rows, manager, config, &mut result)?;
+
+            if eof {
+                return Ok(result);
+            }
+            chunk.truncate(header.len());
+            lines = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        apply_all, csv_reader, load, load_ndjson, read_all, validate_data, TransactionRecord,
+        TransactionRecordBuilder,
+    };
+    use crate::{config::Config, error::TransactorError, manager::Manager, operation::Operation};
+    use std::path::PathBuf;
+
+    const HEADER: &str = "type,client,tx,amount";
+
+    #[test]
+    fn deserialize_deposit() {
+        let entry = "deposit,1,1,100";
+        let csv = format!("{HEADER}\n{entry}");
+        let mut rdr = csv::Reader::from_reader(csv.as_bytes());
+        let mut iter = rdr.deserialize::<TransactionRecord>();
+        let record = iter.next().expect("No Items").expect("Deserialize Failure");
+
+        assert!(matches!(record.operation, Operation::Deposit));
+        assert!(matches!(record.amount, Some(1000000)));
+        assert_eq!(record.client, 1)
+    }
+
+    #[test]
+    fn deserialize_withdraw() {
+        let entry = "withdrawal,1,1,100";
+        let csv = format!("{HEADER}\n{entry}");
+        let mut rdr = csv::Reader::from_reader(csv.as_bytes());
+        let mut iter = rdr.deserialize::<TransactionRecord>();
+        let record = iter.next().expect("No Items").expect("Deserialize Failure");
+
+        assert!(matches!(record.operation, Operation::Withdrawal));
+        assert!(matches!(record.amount, Some(1000000)));
+        assert_eq!(record.client, 1)
+    }
+
+    #[test]
+    fn deserialize_dispute() {
+        let entry = "dispute,1,1,";
+        let csv = format!("{HEADER}\n{entry}");
+        let mut rdr = csv::Reader::from_reader(csv.as_bytes());
+        let mut iter = rdr.deserialize::<TransactionRecord>();
+        let record = iter.next().expect("No Items").expect("Deserialize Failure");
+
+        assert!(matches!(record.operation, Operation::Dispute));
+        assert!(record.amount.is_none());
+        assert_eq!(record.client, 1)
+    }
+
+    #[test]
+    fn deserialize_resolve() {
+        let entry = "resolve,1,1,";
+        let csv = format!("{HEADER}\n{entry}");
+        let mut rdr = csv::Reader::from_reader(csv.as_bytes());
+        let mut iter = rdr.deserialize::<TransactionRecord>();
+        let record = iter.next().expect("No Items").expect("Deserialize Failure");
+
+        assert!(matches!(record.operation, Operation::Resolve));
+        assert!(record.amount.is_none());
+        assert_eq!(record.client, 1)
+    }
+
+    #[test]
+    fn deserialize_chargeback() {
+        let entry = "chargeback,1,1,";
+        let csv = format!("{HEADER}\n{entry}");
+        let mut rdr = csv::Reader::from_reader(csv.as_bytes());
+        let mut iter = rdr.deserialize::<TransactionRecord>();
+        let record = iter.next().expect("No Items").expect("Deserialize Failure");
+
+        assert!(matches!(record.operation, Operation::Chargeback));
+        assert!(record.amount.is_none());
+        assert_eq!(record.client, 1)
+    }
+
+    #[test]
+    fn deserialize_operation_case_insensitive() {
+        let cases = [
+            ("WITHDRAWAL", Operation::Withdrawal),
+            ("Withdrawal", Operation::Withdrawal),
+            ("wItHdRaWaL", Operation::Withdrawal),
+            ("DEPOSIT", Operation::Deposit),
+            ("Deposit", Operation::Deposit),
+            ("dEpOsIt", Operation::Deposit),
+            ("DISPUTE", Operation::Dispute),
+            ("Dispute", Operation::Dispute),
+            ("DiSpUtE", Operation::Dispute),
+            ("RESOLVE", Operation::Resolve),
+            ("Resolve", Operation::Resolve),
+            ("rEsOlVe", Operation::Resolve),
+            ("CHARGEBACK", Operation::Chargeback),
+            ("Chargeback", Operation::Chargeback),
+            ("ChArGeBaCk", Operation::Chargeback),
+        ];
+
+        for (name, operation) in cases {
+            let csv = format!("{HEADER}\n{name},1,1,100");
+            let mut rdr = csv::Reader::from_reader(csv.as_bytes());
+            let mut iter = rdr.deserialize::<TransactionRecord>();
+            let record = iter.next().expect("No Items").expect("Deserialize Failure");
+
+            assert_eq!(record.operation, operation);
+        }
+    }
+
+    #[test]
+    fn truncate_long_dec() {
+        let entry = "deposit,1,1,100.1234567";
+        let csv = format!("{HEADER}\n{entry}");
+        let mut rdr = csv::Reader::from_reader(csv.as_bytes());
+        let mut iter = rdr.deserialize::<TransactionRecord>();
+        let record = iter.next().expect("No Items").expect("Deserialize Failure");
+
+        assert!(matches!(record.operation, Operation::Deposit));
+        assert!(matches!(record.amount, Some(1001234)));
+        assert_eq!(record.client, 1)
+    }
+
+    #[test]
+    fn negative_amount() {
+        let entry = "deposit,1,1,-100";
+        let csv = format!("{HEADER}\n{entry}");
+        let mut rdr = csv::Reader::from_reader(csv.as_bytes());
+        let mut iter = rdr.deserialize::<TransactionRecord>();
+        let result = iter.next().expect("No Items");
+        assert!(result.is_err())
+    }
+
+    #[test]
+    fn padded_fields() {
+        let csv = " type , client , tx , amount \n  withdrawal  ,  2  ,  5  ,  50.00  ";
+        let mut rdr = csv_reader(csv.as_bytes(), &Config::default());
+        let mut iter = rdr.deserialize::<TransactionRecord>();
+        let record = iter.next().expect("No Items").expect("Deserialize Failure");
+
+        assert!(matches!(record.operation, Operation::Withdrawal));
+        assert!(matches!(record.amount, Some(500000)));
+        assert_eq!(record.client, 2);
+        assert_eq!(record.tx, Some(5));
+    }
+
+    #[test]
+    fn tab_delimited() {
+        let csv = "type\tclient\ttx\tamount\ndeposit\t1\t1\t100";
+        let config = Config {
+            delimiter: b'\t',
+            ..Config::default()
+        };
+        let mut rdr = csv_reader(csv.as_bytes(), &config);
+        let mut iter = rdr.deserialize::<TransactionRecord>();
+        let record = iter.next().expect("No Items").expect("Deserialize Failure");
+
+        assert!(matches!(record.operation, Operation::Deposit));
+        assert!(matches!(record.amount, Some(1000000)));
+        assert_eq!(record.client, 1);
+    }
+
+    #[test]
+    fn no_header() {
+        let csv = "deposit,1,1,100\ndispute,1,1,\nresolve,1,1\ndispute,1,1";
+        let config = Config {
+            no_header: true,
+            ..Config::default()
+        };
+
+        let mut mgr = Manager::new();
+        let result = load(csv.as_bytes(), &mut mgr, &config).expect("Failed to load");
+
+        assert_eq!(result.processed, 4);
+        assert_eq!(result.skipped, 0);
+        let acct = mgr.into_iter().next().expect("No Account").1;
+        assert_eq!(acct.held(), 1000000);
+    }
+
+    #[test]
+    fn no_header_parse_error_row() {
+        let csv = "deposit,1,1,100\ndeposit,1,2,one";
+        let config = Config {
+            no_header: true,
+            ..Config::default()
+        };
+
+        let mut mgr = Manager::new();
+        let result = load(csv.as_bytes(), &mut mgr, &config);
+
+        assert!(matches!(
+            result,
+            Err(TransactorError::ParseErrorAt { row: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn byte_order_mark() {
+        let csv = format!("\u{FEFF}{HEADER}\ndeposit,1,1,100");
+
+        let mut mgr = Manager::new();
+        let result = load(csv.as_bytes(), &mut mgr, &Config::default()).expect("Failed to load");
+
+        assert_eq!(result.processed, 1);
+        assert_eq!(result.skipped, 0);
+    }
+
+    #[test]
+    fn shuffled_columns() {
+        let csv = "client,type,amount,tx\n1,deposit,100,2";
+        let mut rdr = csv::Reader::from_reader(csv.as_bytes());
+        let mut iter = rdr.deserialize::<TransactionRecord>();
+        let record = iter.next().expect("No Items").expect("Deserialize Failure");
+
+        assert!(matches!(record.operation, Operation::Deposit));
+        assert!(matches!(record.amount, Some(1000000)));
+        assert_eq!(record.client, 1);
+        assert_eq!(record.tx, Some(2));
+    }
+
+    #[test]
+    fn missing_amount_column() {
+        let csv = "type,client,tx\ndispute,1,1\nresolve,1,1";
+        let mut rdr = csv::Reader::from_reader(csv.as_bytes());
+        let records: Vec<TransactionRecord> = rdr
+            .deserialize()
+            .collect::<Result<_, _>>()
+            .expect("Deserialize Failure");
+
+        assert!(matches!(records[0].operation, Operation::Dispute));
+        assert!(records[0].amount.is_none());
+        assert!(matches!(records[1].operation, Operation::Resolve));
+        assert!(records[1].amount.is_none());
+        assert_eq!(records[1].tx, Some(1));
+    }
+
+    #[test]
+    fn process_transfer() {
+        let csv = "type,client,tx,amount,target\n\
+                   deposit,1,1,100,\n\
+                   transfer,1,2,40,2\n\
+                   transfer,1,3,100,2";
+
+        let mut mgr = Manager::new();
+        let result = load(csv.as_bytes(), &mut mgr, &Config::default()).expect("Failed to load");
+
+        assert_eq!(result.processed, 3);
+        assert!(matches!(
+            result.soft_errors[..],
+            [(3, TransactorError::TransferFailed { from: 1, to: 2, .. })]
+        ));
+
+        let mut accounts: Vec<_> = mgr.into_iter().collect();
+        accounts.sort_by_key(|(client, _)| *client);
+        assert_eq!(accounts[0].1.available(), 600000);
+        assert_eq!(accounts[1].1.available(), 400000);
+    }
+
+    #[test]
+    fn process_fee() {
+        let entries = [
+            "deposit,1,1,100",
+            "fee,1,2,2.5",
+            "deposit,2,3,10",
+            "fee,2,4,20",
+        ];
+        let csv = format!("{HEADER}\n{}", entries.join("\n"));
+
+        let mut mgr = Manager::new();
+        let result = load(csv.as_bytes(), &mut mgr, &Config::default()).expect("Failed to load");
+
+        assert_eq!(result.skipped, 1);
+        assert!(matches!(
+            result.soft_errors[..],
+            [(4, TransactorError::InsufficientFundsForFee { .. })]
+        ));
+
+        let mut accounts: Vec<_> = mgr.into_iter().collect();
+        accounts.sort_by_key(|(client, _)| *client);
+        assert_eq!(accounts[0].1.available(), 975000);
+        assert_eq!(accounts[1].1.available(), 100000);
+    }
+
+    #[test]
+    fn process_register() {
+        let entries = ["register,1,,", "deposit,1,1,100", "register,1,,"];
+        let csv = format!("{HEADER}\n{}", entries.join("\n"));
+
+        let mut mgr = Manager::new();
+        let result = load(csv.as_bytes(), &mut mgr, &Config::default()).expect("Failed to load");
+
+        assert!(matches!(
+            result.soft_errors[..],
+            [(3, TransactorError::ClientAlreadyExists(1))]
+        ));
+        let acct = mgr.into_iter().next().expect("No Account").1;
+        assert_eq!(acct.available(), 1000000);
+    }
+
+    #[test]
+    fn load_precision() {
+        let csv = format!("{HEADER}\ndeposit,1,1,1.239\ndispute,1,1,");
+        let config = Config {
+            decimal_scale: 100,
+            ..Config::default()
+        };
+
+        let mut mgr = Manager::new();
+        load(csv.as_bytes(), &mut mgr, &config).expect("Failed to load");
+
+        let acct = mgr.into_iter().next().expect("No Account").1;
+        assert_eq!(acct.held(), 123);
+    }
+
+    #[test]
+    fn load_admin_unfreeze() {
+        let csv = format!(
+            "{HEADER}\ndeposit,1,1,100\ndeposit,1,2,50\ndispute,1,1,\nchargeback,1,1,\n\
+             admin_unfreeze,1,,\nwithdrawal,1,3,20"
+        );
+
+        let mut mgr = Manager::new();
+        let result = load(csv.as_bytes(), &mut mgr, &Config::default()).expect("Failed to load");
+
+        assert!(result.soft_errors.is_empty());
+        let acct = mgr.into_iter().next().expect("No Account").1;
+        assert!(!acct.is_frozen());
+        assert_eq!(acct.available(), 300000);
+    }
+
+    #[test]
+    fn load_description() {
+        let csv = "type,client,tx,amount,description\ndeposit,1,1,100,INV-1042\ndeposit,1,2,50,";
+
+        let mut mgr = Manager::new();
+        load(csv.as_bytes(), &mut mgr, &Config::default()).expect("Failed to load");
+
+        let acct = mgr.into_iter().next().expect("No Account").1;
+        assert_eq!(acct.deposit_description(1), Some("INV-1042"));
+        assert_eq!(acct.deposit_description(2), None);
+    }
+
+    #[test]
+    fn load_ndjson_records() {
+        let ndjson = "{\"type\":\"deposit\",\"client\":1,\"tx\":1,\"amount\":\"1.25\",\"description\":\"INV-1\"}\n\
+                      \n\
+                      {\"type\":\"withdrawal\",\"client\":1,\"tx\":2,\"amount\":\"5\"}\n";
+        let config = Config {
+            decimal_scale: 100,
+            ..Config::default()
+        };
+
+        let mut mgr = Manager::new();
+        let result = load_ndjson(ndjson.as_bytes(), &mut mgr, &config).expect("Failed to load");
+
+        assert_eq!(result.processed, 2);
+        assert!(matches!(
+            result.soft_errors[..],
+            [(3, TransactorError::WithdrawalExceedsAvailable { .. })]
+        ));
+        let acct = mgr.into_iter().next().expect("No Account").1;
+        assert_eq!(acct.available(), 125);
+        assert_eq!(acct.deposit_description(1), Some("INV-1"));
+
+        let ndjson =
+            "{\"type\":\"deposit\",\"client\":1,\"tx\":1,\"amount\":\"1\"}\n{\"type\":\"deposit\"";
+        assert!(matches!(
+            load_ndjson(ndjson.as_bytes(), &mut Manager::new(), &Config::default()),
+            Err(TransactorError::JsonParseErrorAt { row: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn load_skip_lines() {
+        let csv = format!(
+            "# Generated by Legacy System 1.0\n# Date: 2024-01-01\n{HEADER}\ndeposit,1,1,100\nwithdrawal,1,2,300"
+        );
+        let config = Config {
+            skip_lines: 2,
+            ..Config::default()
+        };
+
+        let mut mgr = Manager::new();
+        let result = load(csv.as_bytes(), &mut mgr, &config).expect("Failed to load");
+
+        assert_eq!(result.processed, 2);
+        assert!(matches!(
+            result.soft_errors[..],
+            [(2, TransactorError::WithdrawalExceedsAvailable { .. })]
+        ));
+        let acct = mgr.into_iter().next().expect("No Account").1;
+        assert_eq!(acct.available(), 1000000);
+    }
+
+    #[test]
+    fn read_all_apply_all() {
+        let csv = format!("{HEADER}\ndeposit,1,1,100\nwithdrawal,1,2,40");
+        let records = read_all(csv.as_bytes()).expect("Failed to read");
+        assert_eq!(records.len(), 2);
+
+        let mut mgr = Manager::new();
+        let result = apply_all(records, &mut mgr, &Config::default());
+        assert_eq!(result.processed, 2);
+        assert!(result.soft_errors.is_empty());
+        let acct = mgr.into_iter().next().expect("No Account").1;
+        assert_eq!(acct.available(), 600000);
+
+        // Reversed, the withdrawal precedes the deposit creating the account
+        let mut records = read_all(csv.as_bytes()).expect("Failed to read");
+        records.reverse();
+
+        let mut mgr = Manager::new();
+        let result = apply_all(records, &mut mgr, &Config::default());
+        assert_eq!(result.processed, 2);
+        assert!(matches!(
+            result.soft_errors[..],
+            [(1, TransactorError::NoClient(1))]
+        ));
+        let acct = mgr.into_iter().next().expect("No Account").1;
+        assert_eq!(acct.available(), 1000000);
+    }
+
+    #[test]
+    fn read_all_parse_error() {
+        let csv = format!("{HEADER}\ndeposit,1,1,100\ndeposit,x,2,100");
+        assert!(matches!(
+            read_all(csv.as_bytes()),
+            Err(TransactorError::ParseErrorAt { row: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn builder() {
+        let record = TransactionRecordBuilder::new(Operation::Deposit)
+            .operation(Operation::Transfer)
+            .client(1)
+            .tx(2)
+            .amount(1.23456)
+            .target(3)
+            .build();
+
+        assert_eq!(record.operation, Operation::Transfer);
+        assert_eq!(record.client, 1);
+        assert_eq!(record.tx, Some(2));
+        assert_eq!(record.amount, Some(12345));
+        assert_eq!(record.target_client, Some(3));
+
+        let record = TransactionRecordBuilder::new(Operation::Dispute).build();
+        assert_eq!(record.client, 0);
+        assert!(record.tx.is_none() && record.amount.is_none() && record.target_client.is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid amount -1")]
+    fn builder_negative_amount() {
+        TransactionRecordBuilder::new(Operation::Deposit).amount(-1.0);
+    }
+
+    #[test]
+    fn process_missing_tx() {
+        let record = TransactionRecordBuilder::new(Operation::Deposit)
+            .client(1)
+            .amount(100.0)
+            .build();
+
+        let mut mgr = Manager::new();
+        let result = record.process(&mut mgr, &Config::default());
+
+        assert!(matches!(result, Err(TransactorError::MissingTxn)));
+    }
+
+    #[test]
+    fn process_missing_target() {
+        let record = TransactionRecordBuilder::new(Operation::Transfer)
+            .client(1)
+            .tx(1)
+            .amount_raw(1000000)
+            .build();
+
+        let mut mgr = Manager::new();
+        let result = record.process(&mut mgr, &Config::default());
+
+        assert!(matches!(result, Err(TransactorError::MissingTarget)));
+    }
+
+    #[test]
+    fn process_missing_amount() {
+        let record = TransactionRecordBuilder::new(Operation::Deposit)
+            .client(1)
+            .tx(1)
+            .build();
+
+        let mut mgr = Manager::new();
+        let result = record.process(&mut mgr, &Config::default());
+
+        assert!(matches!(result, Err(TransactorError::MissingAmount)));
+    }
+
+    #[test]
+    fn validate_record() {
+        let record = |op| TransactionRecordBuilder::new(op).client(1).tx(1);
+
+        assert!(record(Operation::Deposit)
+            .amount(1.0)
+            .build()
+            .validate()
+            .is_ok());
+        assert!(record(Operation::Dispute).build().validate().is_ok());
+        assert!(TransactionRecordBuilder::new(Operation::Register)
+            .client(1)
+            .build()
+            .validate()
+            .is_ok());
+
+        let cases = [
+            (
+                record(Operation::Withdrawal).build(),
+                TransactorError::MissingAmount,
+            ),
+            (
+                record(Operation::Deposit).amount_raw(0).build(),
+                TransactorError::ZeroAmount,
+            ),
+            (
+                record(Operation::Withdrawal).amount(0.00001).build(),
+                TransactorError::ZeroAmount,
+            ),
+            (
+                record(Operation::Dispute).amount(1.0).build(),
+                TransactorError::UnexpectedAmount,
+            ),
+            (
+                record(Operation::Resolve).amount(1.0).build(),
+                TransactorError::UnexpectedAmount,
+            ),
+            (
+                record(Operation::Chargeback).amount(1.0).build(),
+                TransactorError::UnexpectedAmount,
+            ),
+            (
+                record(Operation::Deposit).client(0).amount(1.0).build(),
+                TransactorError::ZeroClient,
+            ),
+            (
+                record(Operation::Deposit).tx(0).amount(1.0).build(),
+                TransactorError::ZeroTxn,
+            ),
+        ];
+
+        for (record, error) in cases {
+            assert_eq!(
+                record.validate().unwrap_err().kind(),
+                error.kind(),
+                "{record:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn process_validates() {
+        let record = TransactionRecordBuilder::new(Operation::Deposit)
+            .client(1)
+            .tx(1)
+            .amount_raw(0)
+            .build();
+
+        let mut mgr = Manager::new();
+        let result = record.process(&mut mgr, &Config::default());
+
+        assert!(matches!(result, Err(TransactorError::ZeroAmount)));
+        assert!(mgr.into_iter().next().is_none());
+    }
+
+    #[test]
+    fn parse_error_row() {
+        let entries = "deposit,1,1,100\ndeposit,1,2,100\ndeposit,one,3,100";
+        let csv = format!("{HEADER}\n{entries}");
+
+        let mut mgr = Manager::new();
+        let result = load(csv.as_bytes(), &mut mgr, &Config::default());
+
+        assert!(matches!(
+            result,
+            Err(TransactorError::ParseErrorAt { row: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn process_result() {
+        let entries = [
+            "deposit,1,1,100",
+            "withdrawal,1,2,150",
+            "deposit,2,3,50",
+            "dispute,2,4,",
+            "withdrawal,1,5,25",
+        ];
+        let csv = format!("{HEADER}\n{}", entries.join("\n"));
+
+        let mut mgr = Manager::new();
+        let result = load(csv.as_bytes(), &mut mgr, &Config::default()).expect("Failed to load");
+
+        assert_eq!(result.processed, 5);
+        assert_eq!(result.skipped, 2);
+        assert!(matches!(
+            result.soft_errors[0],
+            (2, TransactorError::WithdrawalExceedsAvailable { .. })
+        ));
+        assert!(matches!(
+            result.soft_errors[1],
+            (4, TransactorError::NoTransaction(4))
+        ));
+    }
+
+    #[test]
+    fn deposit_limit() {
+        let entries = [
+            "deposit,1,1,100",
+            "deposit,1,2,100",
+            "deposit,2,3,100",
+            "deposit,1,4,100",
+            "deposit,1,5,100",
+        ];
+        let csv = format!("{HEADER}\n{}", entries.join("\n"));
+
+        let config = Config {
+            max_deposits_per_account: Some(2),
+            ..Config::default()
+        };
+        let mut mgr = Manager::from_config(&config);
+        let result = load(csv.as_bytes(), &mut mgr, &config).expect("Failed to load");
+
+        assert_eq!(result.skipped, 2);
+        assert!(matches!(
+            result.soft_errors[..],
+            [
+                (
+                    4,
+                    TransactorError::DepositLimitExceeded {
+                        client: 1,
+                        limit: 2
+                    }
+                ),
+                (
+                    5,
+                    TransactorError::DepositLimitExceeded {
+                        client: 1,
+                        limit: 2
+                    }
+                )
+            ]
+        ));
+    }
+
+    #[test]
+    fn load_malformed_records() {
+        let entries = [
+            "deposit,1,1,100",
+            "deposit,2,2,0",
+            "dispute,1,1,5",
+            "deposit,0,3,100",
+            "withdrawal,1,0,10",
+            "deposit,1,4,50",
+        ];
+        let csv = format!("{HEADER}\n{}", entries.join("\n"));
+
+        let mut mgr = Manager::new();
+        let result = load(csv.as_bytes(), &mut mgr, &Config::default()).expect("Failed to load");
+
+        assert_eq!(result.processed, 6);
+        assert_eq!(result.skipped, 4);
+        assert!(matches!(
+            result.soft_errors[..],
+            [
+                (2, TransactorError::ZeroAmount),
+                (3, TransactorError::UnexpectedAmount),
+                (4, TransactorError::ZeroClient),
+                (5, TransactorError::ZeroTxn)
+            ]
+        ));
+        let accounts: Vec<_> = mgr.into_iter().collect();
+        assert_eq!(accounts.len(), 1);
+        assert_eq!(accounts[0].1.available(), 1500000);
+    }
+
+    #[test]
+    fn load_max_ops_per_client() {
+        let entries = [
+            "deposit,1,1,100",
+            "deposit,2,2,100",
+            "dispute,1,1,",
+            "withdrawal,2,3,50",
+            "resolve,1,1,",
+            "deposit,2,4,10",
+        ];
+        let csv = format!("{HEADER}\n{}", entries.join("\n"));
+
+        let config = Config {
+            max_ops_per_client: Some(2),
+            ..Config::default()
+        };
+        let mut mgr = Manager::from_config(&config);
+        let result = load(csv.as_bytes(), &mut mgr, &config).expect("Failed to load");
+
+        assert_eq!(result.processed, 6);
+        assert!(matches!(
+            result.soft_errors[..],
+            [
+                (5, TransactorError::ClientRateLimited(1)),
+                (6, TransactorError::ClientRateLimited(2))
+            ]
+        ));
+        let accounts: Vec<_> = mgr.into_iter().collect();
+        assert_eq!(accounts[0].1.held(), 1000000);
+        assert_eq!(accounts[1].1.available(), 500000);
+    }
+
+    #[test]
+    fn strict() {
+        let entries = ["deposit,1,1,100", "withdrawal,1,2,150", "deposit,2,3,50"];
+        let csv = format!("{HEADER}\n{}", entries.join("\n"));
+        let config = Config {
+            strict: true,
+            ..Config::default()
+        };
+
+        let mut mgr = Manager::new();
+        let result = load(csv.as_bytes(), &mut mgr, &config);
+
+        assert!(matches!(
+            result,
+            Err(TransactorError::WithdrawalExceedsAvailable { .. })
+        ));
+        assert_eq!(mgr.into_iter().count(), 1);
+    }
+
+    #[test]
+    fn validate() {
+        let entries = [
+            "deposit,1,1,100",
+            "withdrawal,2,2,50",
+            "deposit,one,3,100",
+            "withdrawal,1,4,",
+            "withdrawal,1,5,150",
+            "deposit,1,6,100",
+        ];
+        let csv = format!("{HEADER}\n{}", entries.join("\n"));
+
+        let errors = validate_data(csv.as_bytes(), &Config::default()).expect("Failed to validate");
+
+        assert!(matches!(
+            errors[..],
+            [
+                (2, TransactorError::NoClient(2)),
+                (3, TransactorError::ParseErrorAt { row: 3, .. }),
+                (4, TransactorError::MissingAmount),
+                (5, TransactorError::WithdrawalExceedsAvailable { .. }),
+            ]
+        ));
+    }
+
+    #[test]
+    fn validate_max_ops_per_client() {
+        let entries = [
+            "deposit,1,1,100",
+            "deposit,1,2,100",
+            "withdrawal,1,3,50",
+            "deposit,2,4,100",
+        ];
+        let csv = format!("{HEADER}\n{}", entries.join("\n"));
+
+        let config = Config {
+            max_ops_per_client: Some(1),
+            ..Config::default()
+        };
+        let errors = validate_data(csv.as_bytes(), &config).expect("Failed to validate");
+
+        assert!(matches!(
+            errors[..],
+            [
+                (2, TransactorError::ClientRateLimited(1)),
+                (3, TransactorError::ClientRateLimited(1)),
+            ]
+        ));
+    }
+
+    #[test]
+    fn audit_frozen_deposit() {
+        let entries = [
+            "deposit,1,1,100",
+            "dispute,1,1,",
+            "chargeback,1,1,",
+            "deposit,1,2,50",
+        ];
+        let csv = format!("{HEADER}\n{}", entries.join("\n"));
+
+        let config = Config {
+            audit_log: Some(PathBuf::from("audit.csv")),
+            ..Config::default()
+        };
+        let mut mgr = Manager::from_config(&config);
+        load(csv.as_bytes(), &mut mgr, &config).expect("Failed to load");
+
+        let trail = mgr.audit_trail();
+        assert_eq!(trail.len(), 4);
+        assert!(trail[..3].iter().all(|entry| entry.outcome.is_ok()));
+
+        let entry = &trail[3];
+        assert_eq!(entry.row, 4);
+        assert_eq!(entry.operation, Operation::Deposit);
+        assert_eq!(entry.client, 1);
+        assert_eq!(entry.tx, Some(2));
+        assert_eq!(entry.amount, Some(500000));
+        assert!(matches!(entry.outcome, Err(TransactorError::FrozenAccount)));
+    }
+}
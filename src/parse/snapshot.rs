@@ -0,0 +1,33 @@
+use crate::error::TransactorError;
+use serde::{de::DeserializeOwned, Serialize};
+use std::io::{Read, Write};
+
+/// Serialize the state and write it to the writer as JSON.
+#[cfg(not(feature = "bincode"))]
+#[inline]
+pub fn write_snapshot<T: Serialize, W: Write>(state: &T, writer: W) -> Result<(), TransactorError> {
+    serde_json::to_writer(writer, state)
+        .map_err(|err| TransactorError::SnapshotError(err.to_string()))
+}
+
+/// Read and deserialize JSON state from the reader.
+#[cfg(not(feature = "bincode"))]
+#[inline]
+pub fn read_snapshot<T: DeserializeOwned, R: Read>(reader: R) -> Result<T, TransactorError> {
+    serde_json::from_reader(reader).map_err(|err| TransactorError::SnapshotError(err.to_string()))
+}
+
+/// Serialize the state and write it to the writer as bincode.
+#[cfg(feature = "bincode")]
+#[inline]
+pub fn write_snapshot<T: Serialize, W: Write>(state: &T, writer: W) -> Result<(), TransactorError> {
+    bincode::serialize_into(writer, state)
+        .map_err(|err| TransactorError::SnapshotError(err.to_string()))
+}
+
+/// Read and deserialize bincode state from the reader.
+#[cfg(feature = "bincode")]
+#[inline]
+pub fn read_snapshot<T: DeserializeOwned, R: Read>(reader: R) -> Result<T, TransactorError> {
+    bincode::deserialize_from(reader).map_err(|err| TransactorError::SnapshotError(err.to_string()))
+}
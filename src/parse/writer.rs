@@ -0,0 +1,635 @@
+use crate::{
+    account::Account,
+    audit::AuditEntry,
+    config::{Config, OutputFormat},
+    error::TransactorError,
+    fixed_point::{FixedPoint, DECIMALS},
+    manager::Manager,
+    metrics::Metrics,
+    operation::Operation,
+    stats::Stats,
+};
+use indexmap::IndexMap;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    fmt,
+    io::{self, Write},
+};
+
+/// The representation of a CSV account record.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct AccountRecord {
+    client: u16,
+    available: Decimal,
+    held: Decimal,
+    total: Decimal,
+    locked: bool,
+}
+
+impl AccountRecord {
+    /// Construct the record of the account, with amounts written to the number of decimal places.
+    #[inline]
+    fn new(client: u16, acct: &Account, precision: usize) -> Self {
+        AccountRecord {
+            client,
+            available: Decimal::new(acct.available(), precision),
+            held: Decimal::new(acct.held(), precision),
+            total: Decimal::new(acct.total(), precision),
+            locked: acct.is_frozen(),
+        }
+    }
+}
+
+impl From<(u16, &Account)> for AccountRecord {
+    /// Construct the record of the account, with amounts written to the ten thousandths place.
+    #[inline]
+    fn from((client, acct): (u16, &Account)) -> Self {
+        AccountRecord::new(client, acct, DECIMALS)
+    }
+}
+
+/// A u64 scaled to a number of decimal places, serialized as a decimal string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Decimal {
+    raw: u64,
+    precision: usize,
+}
+
+impl Decimal {
+    #[inline]
+    fn new(raw: u64, precision: usize) -> Self {
+        Decimal { raw, precision }
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let decimal = FixedPoint::from_raw(self.raw).to_string_with_precision(self.precision);
+        f.write_str(&decimal)
+    }
+}
+
+impl Serialize for Decimal {
+    #[inline]
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Decimal {
+    /// Deserialize a decimal string, scaled to the number of decimal places it was written with.
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(d)?;
+        let precision = value.split_once('.').map_or(0, |(_, fract)| fract.len());
+
+        FixedPoint::from_str_with_precision(&value, precision)
+            .map(|amount| Decimal::new(amount.raw(), precision))
+            .map_err(de::Error::custom)
+    }
+}
+
+/// The JSON representation of the aggregate statistics.
+#[derive(Debug, Serialize)]
+struct StatsRecord<'a> {
+    accounts: usize,
+    frozen_accounts: usize,
+    available: Decimal,
+    held: Decimal,
+    total: Decimal,
+    operations: &'a IndexMap<Operation, usize>,
+    soft_errors: usize,
+}
+
+impl<'a> StatsRecord<'a> {
+    /// Construct the record of the statistics, with amounts written to the number of decimal places.
+    #[inline]
+    fn new(stats: &'a Stats, precision: usize) -> Self {
+        StatsRecord {
+            accounts: stats.accounts,
+            frozen_accounts: stats.frozen_accounts,
+            available: Decimal::new(stats.available, precision),
+            held: Decimal::new(stats.held, precision),
+            total: Decimal::new(stats.total, precision),
+            operations: &stats.operations,
+            soft_errors: stats.soft_errors,
+        }
+    }
+}
+
+/// The representation of a CSV audit log record.
+#[derive(Debug, Serialize)]
+struct AuditRecord {
+    row: u64,
+    #[serde(rename = "type")]
+    operation: Operation,
+    client: u16,
+    tx: Option<u32>,
+    amount: Option<Decimal>,
+    outcome: String,
+}
+
+impl AuditRecord {
+    /// Construct the record of the audit log entry, with the amount written to the number of decimal places.
+    #[inline]
+    fn new(entry: &AuditEntry, precision: usize) -> Self {
+        AuditRecord {
+            row: entry.row,
+            operation: entry.operation,
+            client: entry.client,
+            tx: entry.tx,
+            amount: entry.amount.map(|amount| Decimal::new(amount, precision)),
+            outcome: match &entry.outcome {
+                Ok(()) => "ok".to_string(),
+                Err(error) => error.to_string(),
+            },
+        }
+    }
+}
+
+/// For each account record in the `Manager`, serialize and write it to the writer
+/// in the configured output format and precision, sorted by client ID if configured.
+/// In debug builds, the invariants of every account are checked first.
+#[inline]
+pub fn dump<W: Write>(manager: Manager, config: &Config, writer: W) -> Result<(), TransactorError> {
+    dump_ref(&manager, config, writer)
+}
+
+/// Write every account record as `dump` does, borrowing the `Manager` so that it may continue
+/// to process transactions afterwards.
+#[inline]
+pub fn dump_ref<W: Write>(
+    manager: &Manager,
+    config: &Config,
+    writer: W,
+) -> Result<(), TransactorError> {
+    write_accounts_filtered(manager, writer, |_, _| true, config).map(|_| ())
+}
+
+/// Write the account records as `dump_ref` does, including only the accounts for which
+/// the predicate returns true when given the client ID and account.
+/// Returns the number of account records written.
+#[inline]
+pub fn write_accounts_filtered<W, F>(
+    manager: &Manager,
+    writer: W,
+    predicate: F,
+    config: &Config,
+) -> Result<usize, TransactorError>
+where
+    W: Write,
+    F: Fn(u16, &Account) -> bool,
+{
+    #[cfg(debug_assertions)]
+    if let Err(failures) = manager.is_consistent() {
+        panic!("inconsistent account state: {failures:?}");
+    }
+
+    let accounts = if config.sort_output {
+        manager.into_sorted_vec_by(|(a, _), (b, _)| a.cmp(b))
+    } else {
+        manager.accounts_iter().collect()
+    };
+    let records: Vec<AccountRecord> = accounts
+        .into_iter()
+        .filter(|&(client, acct)| predicate(client, acct))
+        .map(|(client, acct)| AccountRecord::new(client, acct, config.precision()))
+        .collect();
+
+    match config.output_format {
+        OutputFormat::Csv => write_csv(&records, writer, config)?,
+        OutputFormat::Ndjson => write_ndjson(&records, writer)?,
+    }
+
+    Ok(records.len())
+}
+
+/// Write every account record as a single JSON array, in the order the accounts were created,
+/// with amounts written to the ten thousandths place.
+#[inline]
+pub fn write_as_json_array<W: Write>(
+    manager: &Manager,
+    mut writer: W,
+) -> Result<(), TransactorError> {
+    let records: Vec<AccountRecord> = manager.accounts_iter().map(AccountRecord::from).collect();
+
+    serde_json::to_writer(&mut writer, &records).map_err(io::Error::from)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Write every account record as a single JSON object keyed by client ID, in the order the accounts
+/// were created, with amounts written to the ten thousandths place.
+#[inline]
+pub fn write_as_json_object<W: Write>(
+    manager: &Manager,
+    mut writer: W,
+) -> Result<(), TransactorError> {
+    let records: IndexMap<u16, AccountRecord> = manager
+        .accounts_iter()
+        .map(|(client, acct)| (client, AccountRecord::from((client, acct))))
+        .collect();
+
+    serde_json::to_writer(&mut writer, &records).map_err(io::Error::from)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Write the account records as CSV using the configured output delimiter.
+/// The header row is omitted for headerless configurations.
+#[inline]
+fn write_csv<W: Write>(
+    records: &[AccountRecord],
+    writer: W,
+    config: &Config,
+) -> Result<(), TransactorError> {
+    let mut wtr = csv::WriterBuilder::new()
+        .delimiter(config.output_delimiter())
+        .has_headers(!config.no_header)
+        .from_writer(writer);
+
+    for record in records {
+        wtr.serialize(record)?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+/// Write the account records as newline delimited JSON objects.
+#[inline]
+fn write_ndjson<W: Write>(records: &[AccountRecord], mut writer: W) -> Result<(), TransactorError> {
+    for record in records {
+        serde_json::to_writer(&mut writer, record).map_err(io::Error::from)?;
+        writeln!(writer)?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// Serialize the metrics and write them to the writer, as a CSV header and row,
+/// or as a single JSON object.
+#[inline]
+pub fn write_metrics<W: Write>(
+    metrics: &Metrics,
+    mut writer: W,
+    format: OutputFormat,
+) -> Result<(), TransactorError> {
+    match format {
+        OutputFormat::Csv => {
+            let mut wtr = csv::Writer::from_writer(writer);
+            wtr.serialize(metrics)?;
+            wtr.flush()?;
+        }
+        OutputFormat::Ndjson => {
+            serde_json::to_writer(&mut writer, metrics).map_err(io::Error::from)?;
+            writeln!(writer)?;
+            writer.flush()?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Write the statistics to the writer, as CSV with one `key,value` row per statistic,
+/// or as a single JSON object. Operation counts are keyed as `operations.<type>` in CSV.
+/// Amounts are written to the number of decimal places.
+#[inline]
+pub fn write_stats<W: Write>(
+    stats: &Stats,
+    mut writer: W,
+    format: OutputFormat,
+    precision: usize,
+) -> Result<(), TransactorError> {
+    let record = StatsRecord::new(stats, precision);
+
+    match format {
+        OutputFormat::Csv => {
+            let mut wtr = csv::Writer::from_writer(writer);
+            wtr.write_record(["key", "value"])?;
+            wtr.write_record(["accounts", &record.accounts.to_string()])?;
+            wtr.write_record(["frozen_accounts", &record.frozen_accounts.to_string()])?;
+            wtr.write_record(["available", &record.available.to_string()])?;
+            wtr.write_record(["held", &record.held.to_string()])?;
+            wtr.write_record(["total", &record.total.to_string()])?;
+
+            for (operation, count) in &stats.operations {
+                wtr.write_record([format!("operations.{operation}"), count.to_string()])?;
+            }
+
+            wtr.write_record(["soft_errors", &stats.soft_errors.to_string()])?;
+            wtr.flush()?;
+        }
+        OutputFormat::Ndjson => {
+            serde_json::to_writer(&mut writer, &record).map_err(io::Error::from)?;
+            writeln!(writer)?;
+            writer.flush()?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Serialize each audit log entry and write it to the writer,
+/// with amounts written to the number of decimal places.
+#[inline]
+pub fn write_audit_log<W: Write>(
+    entries: &[AuditEntry],
+    writer: W,
+    precision: usize,
+) -> Result<(), TransactorError> {
+    let mut wtr = csv::Writer::from_writer(writer);
+
+    for entry in entries {
+        wtr.serialize(AuditRecord::new(entry, precision))?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        dump, dump_ref, write_accounts_filtered, write_as_json_array, write_as_json_object,
+        write_metrics, write_stats, AccountRecord, Decimal,
+    };
+    use crate::{
+        account::Account,
+        config::{Config, OutputFormat},
+        manager::Manager,
+        metrics::Metrics,
+        operation::Operation,
+        stats::Stats,
+    };
+
+    #[test]
+    fn serilaize() {
+        let buf = Vec::new();
+        let mut wtr = csv::Writer::from_writer(buf);
+
+        let record = AccountRecord {
+            client: 1,
+            available: Decimal::new(10000, 4),
+            held: Decimal::new(5000, 4),
+            total: Decimal::new(15000, 4),
+            locked: false,
+        };
+
+        wtr.serialize(record).expect("Failed to serialize");
+        let inner = wtr.into_inner().unwrap();
+        let str = std::str::from_utf8(&inner).expect("Failed to convert");
+        assert_eq!(
+            str,
+            "client,available,held,total,locked\n1,1.0000,0.5000,1.5000,false\n"
+        )
+    }
+
+    #[test]
+    fn record_from_ref() {
+        let acct = Account::new(1, 10000).unwrap();
+        let record = AccountRecord::from((1, &acct));
+
+        assert_eq!(record.total.to_string(), "1.0000");
+        assert_eq!(acct.total(), 10000);
+    }
+
+    #[test]
+    fn unload_ref() {
+        let mut mgr = Manager::new();
+        mgr.deposit(2, 1, 10000, None).expect("Failed to deposit");
+        mgr.deposit(1, 2, 20000, None).expect("Failed to deposit");
+
+        let config = Config {
+            sort_output: true,
+            ..Config::default()
+        };
+        let mut buf = Vec::new();
+        dump_ref(&mgr, &config, &mut buf).expect("Failed to unload");
+        assert_eq!(
+            std::str::from_utf8(&buf).expect("Failed to convert"),
+            "client,available,held,total,locked\n1,2.0000,0.0000,2.0000,false\n\
+             2,1.0000,0.0000,1.0000,false\n"
+        );
+
+        // The manager continues to process transactions after being written
+        mgr.withdraw(2, 3, 5000).expect("Failed to withdraw");
+        let mut buf = Vec::new();
+        dump_ref(&mgr, &Config::default(), &mut buf).expect("Failed to unload");
+        assert_eq!(
+            std::str::from_utf8(&buf).expect("Failed to convert"),
+            "client,available,held,total,locked\n2,0.5000,0.0000,0.5000,false\n\
+             1,2.0000,0.0000,2.0000,false\n"
+        );
+    }
+
+    #[test]
+    fn json_array() {
+        let mut mgr = Manager::new();
+        mgr.deposit(2, 1, 10000, None).expect("Failed to deposit");
+        mgr.deposit(1, 2, 25000, None).expect("Failed to deposit");
+        mgr.dispute(1, 2).expect("Failed to dispute");
+
+        let mut buf = Vec::new();
+        write_as_json_array(&mgr, &mut buf).expect("Failed to write");
+        assert_eq!(
+            std::str::from_utf8(&buf).expect("Failed to convert"),
+            r#"[{"client":2,"available":"1.0000","held":"0.0000","total":"1.0000","locked":false},{"client":1,"available":"0.0000","held":"2.5000","total":"2.5000","locked":false}]"#
+        );
+
+        let records: Vec<AccountRecord> =
+            serde_json::from_slice(&buf).expect("Failed to deserialize");
+        let expected: Vec<AccountRecord> = (&mgr)
+            .into_iter()
+            .map(|(&client, acct)| AccountRecord::from((client, acct)))
+            .collect();
+        assert_eq!(records, expected);
+
+        let mut buf = Vec::new();
+        write_as_json_array(&Manager::new(), &mut buf).expect("Failed to write");
+        assert_eq!(buf, b"[]");
+    }
+
+    #[test]
+    fn json_object() {
+        let mut mgr = Manager::new();
+        mgr.deposit(2, 1, 10000, None).expect("Failed to deposit");
+        mgr.deposit(1, 2, 25000, None).expect("Failed to deposit");
+
+        let mut buf = Vec::new();
+        write_as_json_object(&mgr, &mut buf).expect("Failed to write");
+
+        let records: indexmap::IndexMap<String, AccountRecord> =
+            serde_json::from_slice(&buf).expect("Failed to deserialize");
+        assert_eq!(
+            records.keys().map(String::as_str).collect::<Vec<_>>(),
+            ["2", "1"]
+        );
+        assert_eq!(records["1"].total.to_string(), "2.5000");
+        assert_eq!(records["2"].total.to_string(), "1.0000");
+        assert_eq!(records["2"].client, 2);
+    }
+
+    #[test]
+    fn write_frozen_only() {
+        let mut mgr = Manager::new();
+        for client in 1..=3 {
+            mgr.deposit(client, u32::from(client), 10000, None)
+                .expect("Failed to deposit");
+        }
+        mgr.dispute(2, 2).expect("Failed to dispute");
+        mgr.chargeback(2, 2).expect("Failed to chargeback");
+
+        let mut buf = Vec::new();
+        let written = write_accounts_filtered(
+            &mgr,
+            &mut buf,
+            |_id, acct| acct.is_frozen(),
+            &Config::default(),
+        )
+        .expect("Failed to write");
+
+        assert_eq!(written, 1);
+        assert_eq!(
+            std::str::from_utf8(&buf).expect("Failed to convert"),
+            "client,available,held,total,locked
+2,0.0000,0.0000,0.0000,true
+"
+        );
+
+        let written = write_accounts_filtered(&mgr, Vec::new(), |_, _| false, &Config::default())
+            .expect("Failed to write");
+        assert_eq!(written, 0);
+    }
+
+    #[test]
+    fn unload_pipe_delimited() {
+        let mut mgr = Manager::new();
+        mgr.deposit(1, 1, 10000, None).expect("Failed to deposit");
+
+        let config = Config {
+            output_delimiter: Some(b'|'),
+            ..Config::default()
+        };
+        let mut buf = Vec::new();
+        dump(mgr, &config, &mut buf).expect("Failed to unload");
+
+        assert_eq!(
+            std::str::from_utf8(&buf).expect("Failed to convert"),
+            "client|available|held|total|locked\n1|1.0000|0.0000|1.0000|false\n"
+        )
+    }
+
+    #[test]
+    fn unload_sorted_ndjson() {
+        let mut mgr = Manager::new();
+        for client in [3, 1, 2] {
+            mgr.deposit(client, u32::from(client), 10000, None)
+                .expect("Failed to deposit");
+        }
+
+        let config = Config {
+            sort_output: true,
+            output_format: OutputFormat::Ndjson,
+            ..Config::default()
+        };
+        let mut buf = Vec::new();
+        dump(mgr, &config, &mut buf).expect("Failed to unload");
+
+        let lines: Vec<&str> = std::str::from_utf8(&buf)
+            .expect("Failed to convert")
+            .lines()
+            .collect();
+        assert_eq!(
+            lines,
+            [1, 2, 3].map(|client| format!(
+                r#"{{"client":{client},"available":"1.0000","held":"0.0000","total":"1.0000","locked":false}}"#
+            ))
+        );
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "inconsistent account state")]
+    fn unload_inconsistent() {
+        let mgr: Manager = serde_json::from_str(
+            r#"{"accounts":{"1":{"available":"1.0000","held":"1.0000","frozen":false,"deposits":{}}}}"#,
+        )
+        .expect("Failed to deserialize");
+
+        dump(mgr, &Config::default(), Vec::new()).ok();
+    }
+
+    #[test]
+    fn unload_precision() {
+        let mut mgr = Manager::new();
+        mgr.deposit(1, 1, 123, None).expect("Failed to deposit");
+
+        let config = Config {
+            decimal_scale: 100,
+            ..Config::default()
+        };
+        let mut buf = Vec::new();
+        dump(mgr, &config, &mut buf).expect("Failed to unload");
+
+        assert_eq!(
+            std::str::from_utf8(&buf).expect("Failed to convert"),
+            "client,available,held,total,locked\n1,1.23,0.00,1.23,false\n"
+        )
+    }
+
+    #[test]
+    fn write_metrics_formats() {
+        let metrics = Metrics {
+            deposits_ok: 2,
+            withdrawals_err: 1,
+            ..Metrics::default()
+        };
+
+        let mut buf = Vec::new();
+        write_metrics(&metrics, &mut buf, OutputFormat::Csv).expect("Failed to write");
+        assert_eq!(
+            std::str::from_utf8(&buf).expect("Failed to convert"),
+            "deposits_ok,deposits_err,withdrawals_ok,withdrawals_err,disputes_ok,disputes_err,\
+             resolves_ok,resolves_err,chargebacks_ok,chargebacks_err\n2,0,0,1,0,0,0,0,0,0\n"
+        );
+
+        let mut buf = Vec::new();
+        write_metrics(&metrics, &mut buf, OutputFormat::Ndjson).expect("Failed to write");
+        let json: serde_json::Value = serde_json::from_slice(&buf).expect("Invalid JSON");
+        assert_eq!(json["deposits_ok"], 2);
+        assert_eq!(json["withdrawals_err"], 1);
+    }
+
+    #[test]
+    fn write_stats_formats() {
+        let stats = Stats {
+            accounts: 2,
+            frozen_accounts: 1,
+            available: 15000,
+            held: 5000,
+            total: 20000,
+            operations: Operation::ALL.iter().map(|&op| (op, 0)).collect(),
+            soft_errors: 3,
+        };
+
+        let mut buf = Vec::new();
+        write_stats(&stats, &mut buf, OutputFormat::Csv, 4).expect("Failed to write");
+        assert_eq!(
+            std::str::from_utf8(&buf).expect("Failed to convert"),
+            "key,value\naccounts,2\nfrozen_accounts,1\navailable,1.5000\nheld,0.5000\n\
+             total,2.0000\noperations.withdrawal,0\noperations.deposit,0\noperations.dispute,0\n\
+             operations.resolve,0\noperations.chargeback,0\noperations.transfer,0\n\
+             operations.fee,0\noperations.register,0\noperations.admin_unfreeze,0\n\
+             operations.admin_freeze,0\nsoft_errors,3\n"
+        );
+
+        let mut buf = Vec::new();
+        write_stats(&stats, &mut buf, OutputFormat::Ndjson, 4).expect("Failed to write");
+        let json: serde_json::Value = serde_json::from_slice(&buf).expect("Invalid JSON");
+        assert_eq!(json["accounts"], 2);
+        assert_eq!(json["total"], "2.0000");
+        assert_eq!(json["operations"]["deposit"], 0);
+        assert_eq!(json["soft_errors"], 3);
+    }
+}
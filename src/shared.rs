@@ -0,0 +1,190 @@
+//! A `Manager` shared between threads behind a single read-write lock.
+
+use crate::{error::TransactorError, manager::Manager};
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// A handle to a `Manager` that may be cloned and sent across threads.
+/// Every operation takes the write lock for its duration, so operations are applied one at a time,
+/// while any number of readers may inspect the accounts at once.
+/// If an operation panics while holding the write lock, the `Manager` may be left partially updated,
+/// so every later operation is refused with a `LockPoisoned` error rather than exposing it.
+/// Prefer `ConcurrentManager` from the `concurrent` feature when throughput is critical.
+#[derive(Debug, Clone, Default)]
+pub struct SharedManager(Arc<RwLock<Manager>>);
+
+impl SharedManager {
+    /// Construct a new `SharedManager` wrapping the `Manager`.
+    #[inline]
+    pub fn new(manager: Manager) -> Self {
+        SharedManager(Arc::new(RwLock::new(manager)))
+    }
+
+    /// Acquire the read lock, refused if a writer panicked.
+    #[inline]
+    pub fn read(&self) -> Result<RwLockReadGuard<'_, Manager>, TransactorError> {
+        self.0.read().map_err(|_| TransactorError::LockPoisoned)
+    }
+
+    /// Acquire the write lock, refused if a writer panicked.
+    #[inline]
+    pub fn write(&self) -> Result<RwLockWriteGuard<'_, Manager>, TransactorError> {
+        self.0.write().map_err(|_| TransactorError::LockPoisoned)
+    }
+
+    /// Get a copy of the `Manager` as of the moment the read lock is acquired.
+    #[inline]
+    pub fn snapshot(&self) -> Result<Manager, TransactorError> {
+        Ok(self.read()?.clone())
+    }
+
+    /// Create an empty account for the client ID.
+    #[inline]
+    pub fn register_client(&self, client: u16) -> Result<(), TransactorError> {
+        self.write()?.register_client(client)
+    }
+
+    /// Deposit funds into the account specified by the client ID, optionally tagged with a description.
+    #[inline]
+    pub fn deposit(
+        &self,
+        client: u16,
+        tx: u32,
+        amt: u64,
+        description: Option<String>,
+    ) -> Result<(), TransactorError> {
+        self.write()?.deposit(client, tx, amt, description)
+    }
+
+    /// Withdraw funds from the account specified by the client ID, tracking the withdrawal by its transaction ID.
+    #[inline]
+    pub fn withdraw(&self, client: u16, tx: u32, amt: u64) -> Result<(), TransactorError> {
+        self.write()?.withdraw(client, tx, amt)
+    }
+
+    /// Transfer funds from one client's account to another's.
+    #[inline]
+    pub fn transfer(&self, from: u16, to: u16, tx: u32, amt: u64) -> Result<(), TransactorError> {
+        self.write()?.transfer(from, to, tx, amt)
+    }
+
+    /// Dispute a transaction according to the client and transaction ID pair.
+    #[inline]
+    pub fn dispute(&self, client: u16, tx: u32) -> Result<(), TransactorError> {
+        self.write()?.dispute(client, tx)
+    }
+
+    /// Resolve a dispute according to the client and transaction ID pair.
+    #[inline]
+    pub fn resolve(&self, client: u16, tx: u32) -> Result<(), TransactorError> {
+        self.write()?.resolve(client, tx)
+    }
+
+    /// Chargeback a disputed transaction according to the client and transaction ID pair.
+    #[inline]
+    pub fn chargeback(&self, client: u16, tx: u32) -> Result<(), TransactorError> {
+        self.write()?.chargeback(client, tx)
+    }
+}
+
+impl From<Manager> for SharedManager {
+    #[inline]
+    fn from(manager: Manager) -> Self {
+        SharedManager::new(manager)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SharedManager;
+    use crate::{error::TransactorError, manager::Manager};
+    use std::{sync::mpsc, thread, time::Duration};
+
+    #[test]
+    fn operations_across_threads() {
+        let shared = SharedManager::default();
+
+        let handles: Vec<_> = (0..4)
+            .map(|client| {
+                let shared = shared.clone();
+                thread::spawn(move || {
+                    shared
+                        .deposit(client, u32::from(client), 100, None)
+                        .expect("Failed to deposit");
+                    shared
+                        .withdraw(client, 4 + u32::from(client), 40)
+                        .expect("Failed to withdraw");
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.join().expect("Thread panicked");
+        }
+
+        let snapshot = shared.snapshot().expect("Failed to read");
+        assert_eq!(snapshot.account_count(), 4);
+        assert!(snapshot.into_iter().all(|(_, acct)| acct.available() == 60));
+    }
+
+    #[test]
+    fn snapshot_is_independent() {
+        let shared = SharedManager::from(Manager::new());
+        shared.deposit(1, 1, 100, None).expect("Failed to deposit");
+
+        let snapshot = shared.snapshot().expect("Failed to read");
+        shared.deposit(2, 2, 100, None).expect("Failed to deposit");
+
+        assert_eq!(snapshot.account_count(), 1);
+        assert_eq!(shared.read().expect("Failed to read").account_count(), 2);
+    }
+
+    #[test]
+    fn concurrent_reads() {
+        let shared = SharedManager::default();
+        shared.deposit(1, 1, 100, None).expect("Failed to deposit");
+
+        // Another thread may read while this one holds the read lock
+        let guard = shared.read().expect("Failed to read");
+        let (sender, receiver) = mpsc::channel();
+        let reader = {
+            let shared = shared.clone();
+            thread::spawn(move || sender.send(shared.snapshot().map(|mgr| mgr.account_count())))
+        };
+
+        assert!(matches!(
+            receiver.recv_timeout(Duration::from_secs(5)),
+            Ok(Ok(1))
+        ));
+        assert_eq!(guard.account_count(), 1);
+        drop(guard);
+        reader
+            .join()
+            .expect("Thread panicked")
+            .expect("Failed to send");
+    }
+
+    #[test]
+    fn poisoned_lock() {
+        let shared = SharedManager::default();
+        shared.deposit(1, 1, 100, None).expect("Failed to deposit");
+
+        let panicked = {
+            let shared = shared.clone();
+            thread::spawn(move || {
+                let _guard = shared.write().expect("Failed to write");
+                panic!("panic while holding the write lock");
+            })
+            .join()
+        };
+        assert!(panicked.is_err());
+
+        assert!(matches!(
+            shared.deposit(1, 2, 100, None),
+            Err(TransactorError::LockPoisoned)
+        ));
+        assert!(matches!(
+            shared.snapshot(),
+            Err(TransactorError::LockPoisoned)
+        ));
+    }
+}
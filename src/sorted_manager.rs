@@ -0,0 +1,259 @@
+//! An account manager that always iterates accounts in ascending order of client ID.
+
+use crate::{
+    account::Account,
+    config::Config,
+    error::TransactorError,
+    manager::{check_amount, deposit_limited, open_account, Manager},
+};
+use std::collections::{
+    btree_map::{Entry, IntoIter, Iter},
+    BTreeMap,
+};
+
+/// Account manager associating a client ID to an account, held in a `BTreeMap`.
+/// Accounts are always iterated in ascending order of client ID, without a sort step,
+/// at the cost of O(log n) lookups rather than the O(1) lookups of `Manager`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SortedManager {
+    accounts: BTreeMap<u16, Account>,
+    max_deposits: Option<usize>,
+    max_amount: Option<u64>,
+}
+
+impl SortedManager {
+    /// Construct a new `SortedManager`.
+    #[inline]
+    pub fn new() -> Self {
+        SortedManager::default()
+    }
+
+    /// Construct a new `SortedManager` with the deposit and withdrawal limits of the `Config`.
+    #[inline]
+    pub fn from_config(config: &Config) -> Self {
+        SortedManager {
+            accounts: BTreeMap::new(),
+            max_deposits: config.max_deposits_per_account,
+            max_amount: config.max_transaction_amount,
+        }
+    }
+
+    /// Get the account specified by the client ID.
+    #[inline]
+    pub fn account(&self, client: u16) -> Option<&Account> {
+        self.accounts.get(&client)
+    }
+
+    /// Get the number of accounts.
+    #[inline]
+    pub fn account_count(&self) -> usize {
+        self.accounts.len()
+    }
+
+    /// Return whether there are no accounts.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+    }
+
+    /// Apply the operation to the account specified by the client ID.
+    #[inline]
+    fn with_account<F>(&mut self, client: u16, f: F) -> Result<(), TransactorError>
+    where
+        F: FnOnce(&mut Account) -> Result<(), TransactorError>,
+    {
+        let acct = self
+            .accounts
+            .get_mut(&client)
+            .ok_or(TransactorError::NoClient(client))?;
+        f(acct)
+    }
+
+    /// Deposit funds into the account specified by the client ID, optionally tagged with a description.
+    /// A new account is only created if the deposit succeeds.
+    /// Deposits are limited as in `Manager::deposit`.
+    #[inline]
+    pub fn deposit(
+        &mut self,
+        client: u16,
+        tx: u32,
+        amt: u64,
+        description: Option<String>,
+    ) -> Result<(), TransactorError> {
+        let (limit, max) = (self.max_deposits, self.max_amount);
+
+        match self.accounts.entry(client) {
+            Entry::Occupied(entry) => {
+                deposit_limited(entry.into_mut(), client, tx, amt, description, limit, max)
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(open_account(client, tx, amt, description, limit, max)?);
+                Ok(())
+            }
+        }
+    }
+
+    /// Withdraw funds from the account specified by the client ID, tracking the withdrawal by its transaction ID.
+    /// Withdrawals are limited as in `Manager::withdraw`.
+    #[inline]
+    pub fn withdraw(&mut self, client: u16, tx: u32, amt: u64) -> Result<(), TransactorError> {
+        check_amount(amt, self.max_amount)?;
+        self.with_account(client, |acct| acct.withdraw(tx, amt))
+    }
+
+    /// Dispute a transaction according to the client and transaction ID pair.
+    #[inline]
+    pub fn dispute(&mut self, client: u16, tx: u32) -> Result<(), TransactorError> {
+        self.with_account(client, |acct| acct.dispute(tx))
+    }
+
+    /// Resolve a dispute according to the client and transaction ID pair.
+    #[inline]
+    pub fn resolve(&mut self, client: u16, tx: u32) -> Result<(), TransactorError> {
+        self.with_account(client, |acct| acct.resolve(tx))
+    }
+
+    /// Chargeback a disputed transaction according to the client and transaction ID pair.
+    #[inline]
+    pub fn chargeback(&mut self, client: u16, tx: u32) -> Result<(), TransactorError> {
+        self.with_account(client, |acct| acct.chargeback(tx))
+    }
+
+    /// Consume the `SortedManager`, moving every account into a `Manager` in ascending order of client ID.
+    #[inline]
+    pub fn into_manager(self) -> Manager {
+        self.accounts.into_iter().collect()
+    }
+}
+
+impl IntoIterator for SortedManager {
+    type Item = (u16, Account);
+    type IntoIter = IntoIter<u16, Account>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.accounts.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a SortedManager {
+    type Item = (&'a u16, &'a Account);
+    type IntoIter = Iter<'a, u16, Account>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.accounts.iter()
+    }
+}
+
+impl From<Manager> for SortedManager {
+    #[inline]
+    fn from(manager: Manager) -> Self {
+        SortedManager {
+            accounts: manager.into_iter().collect(),
+            ..SortedManager::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SortedManager;
+    use crate::{config::Config, error::TransactorError, manager::Manager};
+
+    #[test]
+    fn sorted_iteration() {
+        let mut mgr = SortedManager::new();
+        for client in [3, 1, 4, 2] {
+            mgr.deposit(client, u32::from(client), 100, None)
+                .expect("Failed to deposit");
+        }
+
+        let clients: Vec<u16> = mgr.into_iter().map(|(client, _)| client).collect();
+        assert_eq!(clients, [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn dispute_chargeback() {
+        let mut mgr = SortedManager::new();
+        mgr.deposit(1, 1, 100, None).expect("Failed to deposit");
+        mgr.deposit(1, 2, 50, None).expect("Failed to deposit");
+        mgr.dispute(1, 1).expect("Failed to dispute");
+        mgr.dispute(1, 2).expect("Failed to dispute");
+        mgr.resolve(1, 2).expect("Failed to resolve");
+        mgr.withdraw(1, 3, 20).expect("Failed to withdraw");
+        mgr.chargeback(1, 1).expect("Failed to chargeback");
+
+        let acct = mgr.account(1).expect("No Account");
+        assert_eq!(acct.available(), 30);
+        assert_eq!(acct.held(), 0);
+        assert!(acct.is_frozen());
+        assert!(matches!(
+            mgr.withdraw(2, 4, 10),
+            Err(TransactorError::NoClient(2))
+        ));
+        assert_eq!(mgr.account_count(), 1);
+    }
+
+    #[test]
+    fn deposit_limits() {
+        let config = Config {
+            max_deposits_per_account: Some(2),
+            max_transaction_amount: Some(1000),
+            ..Config::default()
+        };
+        let mut mgr = SortedManager::from_config(&config);
+
+        assert!(matches!(
+            mgr.deposit(1, 1, 0, None),
+            Err(TransactorError::ZeroAmount)
+        ));
+        assert!(matches!(
+            mgr.deposit(1, u32::MAX, 100, None),
+            Err(TransactorError::TxIdSpaceExhausted)
+        ));
+        assert!(matches!(
+            mgr.deposit(1, 1, 1001, None),
+            Err(TransactorError::AmountTooLarge {
+                amount: 1001,
+                max: 1000
+            })
+        ));
+        assert!(mgr.is_empty());
+
+        mgr.deposit(1, 1, 100, None).expect("Failed to deposit");
+        mgr.deposit(1, 2, 100, None).expect("Failed to deposit");
+        assert!(matches!(
+            mgr.deposit(1, 3, 100, None),
+            Err(TransactorError::DepositLimitExceeded {
+                client: 1,
+                limit: 2
+            })
+        ));
+        assert!(matches!(
+            mgr.withdraw(1, 3, 1001),
+            Err(TransactorError::AmountTooLarge {
+                amount: 1001,
+                max: 1000
+            })
+        ));
+        assert_eq!(mgr.account(1).expect("No Account").available(), 200);
+    }
+
+    #[test]
+    fn from_manager() {
+        let mut manager = Manager::new();
+        for client in [2, 1] {
+            manager
+                .deposit(client, u32::from(client), 100, None)
+                .expect("Failed to deposit");
+        }
+
+        let mgr = SortedManager::from(manager);
+        let clients: Vec<u16> = (&mgr).into_iter().map(|(&client, _)| client).collect();
+        assert_eq!(clients, [1, 2]);
+
+        let manager = mgr.into_manager();
+        assert_eq!(manager.client_ids().collect::<Vec<_>>(), [1, 2]);
+    }
+}
@@ -0,0 +1,99 @@
+use crate::{manager::Manager, operation::Operation, parse::ProcessResult};
+use indexmap::IndexMap;
+
+/// Aggregate statistics over every account and the records applied to them.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Stats {
+    /// The number of accounts.
+    pub accounts: usize,
+
+    /// The number of frozen accounts.
+    pub frozen_accounts: usize,
+
+    /// The sum of available funds across every account.
+    pub available: u64,
+
+    /// The sum of held funds across every account.
+    pub held: u64,
+
+    /// The sum of total funds across every account.
+    pub total: u64,
+
+    /// The number of records read of each operation type, in declaration order.
+    pub operations: IndexMap<Operation, usize>,
+
+    /// The number of records refused due to a soft error.
+    pub soft_errors: usize,
+}
+
+impl Stats {
+    /// Collect the statistics of the accounts in the `Manager` and the outcome of loading the records.
+    /// Sums saturate rather than overflow.
+    #[inline]
+    pub fn new(manager: &Manager, result: &ProcessResult) -> Self {
+        let mut stats = Stats {
+            operations: Operation::ALL
+                .iter()
+                .map(|op| (*op, result.operations.get(op).copied().unwrap_or_default()))
+                .collect(),
+            soft_errors: result.skipped,
+            ..Stats::default()
+        };
+
+        for (_, acct) in manager {
+            stats.accounts += 1;
+            stats.frozen_accounts += usize::from(acct.is_frozen());
+            stats.available = stats.available.saturating_add(acct.available());
+            stats.held = stats.held.saturating_add(acct.held());
+            stats.total = stats.total.saturating_add(acct.total());
+        }
+
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Stats;
+    use crate::{config::Config, manager::Manager, operation::Operation, parse::load};
+
+    #[test]
+    fn collect() {
+        let entries = [
+            "type,client,tx,amount",
+            "deposit,1,1,100",
+            "deposit,2,2,50",
+            "withdrawal,1,3,150",
+            "dispute,2,2,",
+            "chargeback,2,2,",
+            "deposit,3,4,25.5",
+            "dispute,3,4,",
+        ];
+        let mut mgr = Manager::new();
+        let result = load(entries.join("\n").as_bytes(), &mut mgr, &Config::default())
+            .expect("Failed to load");
+
+        let stats = Stats::new(&mgr, &result);
+        assert_eq!(stats.accounts, 3);
+        assert_eq!(stats.frozen_accounts, 1);
+        assert_eq!(stats.available, 1_000_000);
+        assert_eq!(stats.held, 255_000);
+        assert_eq!(stats.total, 1_255_000);
+        assert_eq!(stats.soft_errors, 1);
+        assert_eq!(
+            stats.operations.into_iter().collect::<Vec<_>>(),
+            [
+                (Operation::Withdrawal, 1),
+                (Operation::Deposit, 3),
+                (Operation::Dispute, 2),
+                (Operation::Resolve, 0),
+                (Operation::Chargeback, 1),
+                (Operation::Transfer, 0),
+                (Operation::Fee, 0),
+                (Operation::Register, 0),
+                (Operation::AdminUnfreeze, 0),
+                (Operation::AdminFreeze, 0),
+            ]
+        );
+    }
+}
@@ -0,0 +1,92 @@
+use crate::{error::TransactorError, manager::Manager, operation::Operation};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// An operation applied to a `Manager`.
+/// Registrations and administrative freezes and unfreezes are not linked to a transaction ID, so theirs is recorded as 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogEntry {
+    pub operation: Operation,
+    pub client: u16,
+    pub tx: u32,
+    pub amount: Option<u64>,
+}
+
+impl LogEntry {
+    /// Construct a new `LogEntry`.
+    #[inline]
+    pub fn new(operation: Operation, client: u16, tx: u32, amount: Option<u64>) -> Self {
+        LogEntry {
+            operation,
+            client,
+            tx,
+            amount,
+        }
+    }
+}
+
+/// A log of every operation applied to a `Manager`, in the order they were applied.
+/// Refused operations are not recorded. Clones share the same log.
+#[derive(Debug, Clone, Default)]
+pub struct TransactionLog {
+    entries: Arc<Mutex<Vec<LogEntry>>>,
+}
+
+impl TransactionLog {
+    /// Lock the entries, recovering them if a writer panicked.
+    #[inline]
+    fn lock(&self) -> MutexGuard<'_, Vec<LogEntry>> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Append an applied operation to the log.
+    #[inline]
+    pub(crate) fn push(&self, entry: LogEntry) {
+        self.lock().push(entry);
+    }
+
+    /// Get a copy of every entry in the log.
+    #[inline]
+    pub fn entries(&self) -> Vec<LogEntry> {
+        self.lock().clone()
+    }
+
+    /// Get the number of entries in the log.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Return whether the log has no entries.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Rebuild the accounts by applying every entry to a new `Manager`.
+    /// Transfers are recorded as a withdrawal and a deposit, and fees were only recorded
+    /// if they were charged, so each entry should apply cleanly to the rebuilt accounts.
+    /// Otherwise, the index of the first refused entry is returned with its error.
+    #[inline]
+    pub fn replay(self) -> Result<Manager, (usize, TransactorError)> {
+        let mut manager = Manager::new();
+
+        for (i, entry) in self.entries().into_iter().enumerate() {
+            let LogEntry {
+                operation,
+                client,
+                tx,
+                amount,
+            } = entry;
+
+            match (operation, amount) {
+                (Operation::Fee, Some(amt)) => manager.apply_fee(client, tx, amt, true),
+                _ => manager.apply_record(operation, client, tx, amount),
+            }
+            .map_err(|error| (i, error))?;
+        }
+
+        Ok(manager)
+    }
+}
@@ -0,0 +1,96 @@
+use crate::account::Account;
+use std::collections::VecDeque;
+
+/// The state of each account affected by an operation, captured before it was applied.
+/// An absent account did not exist before the operation.
+pub type Snapshot = Vec<(u16, Option<Account>)>;
+
+/// A bounded stack of snapshots, one for each operation applied to a `Manager`.
+/// When the depth is exceeded, the oldest snapshot is dropped. A depth of 0 disables the stack.
+#[derive(Debug, Clone, Default)]
+pub struct UndoStack {
+    depth: usize,
+    snapshots: VecDeque<Snapshot>,
+}
+
+impl UndoStack {
+    /// Construct a new `UndoStack` holding at most `depth` snapshots.
+    #[inline]
+    pub fn new(depth: usize) -> Self {
+        UndoStack {
+            depth,
+            snapshots: VecDeque::with_capacity(depth),
+        }
+    }
+
+    /// Return whether snapshots are recorded.
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.depth > 0
+    }
+
+    /// Get the number of snapshots available to undo.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    /// Return whether there are no snapshots available to undo.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    /// Push a snapshot, dropping the oldest if the depth is exceeded.
+    #[inline]
+    pub fn push(&mut self, snapshot: Snapshot) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        if self.snapshots.len() == self.depth {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(snapshot);
+    }
+
+    /// Discard every snapshot.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+    }
+
+    /// Pop the most recent snapshot.
+    #[inline]
+    pub fn pop(&mut self) -> Option<Snapshot> {
+        self.snapshots.pop_back()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UndoStack;
+
+    #[test]
+    fn disabled() {
+        let mut stack = UndoStack::default();
+        stack.push(vec![(1, None)]);
+
+        assert!(!stack.is_enabled());
+        assert!(stack.is_empty());
+        assert!(stack.pop().is_none());
+    }
+
+    #[test]
+    fn drop_oldest() {
+        let mut stack = UndoStack::new(2);
+        for client in 1..=3 {
+            stack.push(vec![(client, None)]);
+        }
+
+        assert_eq!(stack.len(), 2);
+        assert_eq!(stack.pop(), Some(vec![(3, None)]));
+        assert_eq!(stack.pop(), Some(vec![(2, None)]));
+        assert_eq!(stack.pop(), None);
+    }
+}
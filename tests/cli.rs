@@ -0,0 +1,578 @@
+use std::{
+    fs,
+    io::Write,
+    path::PathBuf,
+    process::{Command, Output, Stdio},
+};
+
+const HEADER: &str = "type,client,tx,amount";
+
+/// Write the CSV entries to a temporary file named after the test.
+fn write_csv(name: &str, entries: &[&str]) -> PathBuf {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(format!("{name}.csv"));
+    let csv = format!("{HEADER}\n{}\n", entries.join("\n"));
+    fs::write(&path, csv).expect("Failed to write csv");
+    path
+}
+
+/// Run the transactor binary with the provided arguments.
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_transactor"))
+        .args(args)
+        .output()
+        .expect("Failed to run transactor")
+}
+
+fn stderr(output: &Output) -> &str {
+    std::str::from_utf8(&output.stderr).expect("Invalid stderr")
+}
+
+const ENTRIES: &[&str] = &["deposit,1,1,100", "withdrawal,1,2,150", "deposit,2,3,50"];
+
+#[test]
+fn version() {
+    for flag in ["--version", "-V"] {
+        let output = run(&[flag]);
+
+        assert!(output.status.success());
+        assert_eq!(
+            std::str::from_utf8(&output.stdout).expect("Invalid stdout"),
+            format!("transactor {}\n", env!("CARGO_PKG_VERSION"))
+        );
+    }
+}
+
+#[test]
+fn help() {
+    let output = run(&["--help"]);
+    let stdout = std::str::from_utf8(&output.stdout).expect("Invalid stdout");
+
+    assert!(output.status.success());
+    assert!(stdout.contains("Usage: transactor [OPTIONS] [INPUT]"));
+    assert!(stdout.contains("validate"));
+    assert!(stdout.contains("--precision <N>"));
+}
+
+#[test]
+fn quiet() {
+    let path = write_csv("quiet", ENTRIES);
+    let output = run(&["--quiet", path.to_str().unwrap()]);
+
+    assert!(output.status.success());
+    assert_eq!(stderr(&output), "");
+}
+
+#[test]
+fn normal() {
+    let path = write_csv("normal", ENTRIES);
+    let output = run(&[path.to_str().unwrap()]);
+
+    assert!(output.status.success());
+    assert_eq!(
+        stderr(&output),
+        "row 2: withdrawal client 1: attempt to debit amount of 1500000 exceeds avaiable funds of 1000000\n"
+    );
+}
+
+#[test]
+fn verbose() {
+    let path = write_csv("verbose", ENTRIES);
+    let output = run(&["--verbose", path.to_str().unwrap()]);
+
+    assert!(output.status.success());
+    assert_eq!(
+        stderr(&output),
+        "row 1: deposit client 1: ok\n\
+         row 2: withdrawal client 1: attempt to debit amount of 1500000 exceeds avaiable funds of 1000000\n\
+         row 3: deposit client 2: ok\n\
+         Processed 3 records, skipped 1\n"
+    );
+}
+
+#[test]
+fn fatal_error_when_quiet() {
+    let path = write_csv("fatal_error_when_quiet", &["deposit,one,1,100"]);
+    let output = run(&["--quiet", path.to_str().unwrap()]);
+
+    assert!(!output.status.success());
+    assert!(stderr(&output).starts_with("Fatal Error: parse error at row 1"));
+}
+
+#[test]
+fn audit_log() {
+    let path = write_csv("audit_log", ENTRIES);
+    let audit = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("audit_log.audit.csv");
+    let output = run(&[
+        "--quiet",
+        "--audit-log",
+        audit.to_str().unwrap(),
+        path.to_str().unwrap(),
+    ]);
+
+    assert!(output.status.success());
+    assert_eq!(
+        fs::read_to_string(audit).expect("Failed to read audit log"),
+        "row,type,client,tx,amount,outcome\n\
+         1,deposit,1,1,100.0000,ok\n\
+         2,withdrawal,1,2,150.0000,attempt to debit amount of 1500000 exceeds avaiable funds of 1000000\n\
+         3,deposit,2,3,50.0000,ok\n"
+    );
+}
+
+#[test]
+fn client_filter() {
+    let path = write_csv("client_filter", ENTRIES);
+    let output = run(&[
+        "--quiet",
+        "--client",
+        "1",
+        "-c",
+        "42",
+        path.to_str().unwrap(),
+    ]);
+
+    assert!(output.status.success());
+    assert_eq!(
+        std::str::from_utf8(&output.stdout).expect("Invalid stdout"),
+        "client,available,held,total,locked\n1,100.0000,0.0000,100.0000,false\n"
+    );
+}
+
+#[test]
+fn stdin_stdout() {
+    let csv = format!("{HEADER}\n{}\n", ENTRIES.join("\n"));
+    let mut child = Command::new(env!("CARGO_BIN_EXE_transactor"))
+        .args(["--quiet", "--client", "2", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("Failed to run transactor");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(csv.as_bytes())
+        .expect("Failed to write stdin");
+    let output = child.wait_with_output().expect("Failed to wait");
+
+    assert!(output.status.success());
+    assert_eq!(
+        std::str::from_utf8(&output.stdout).expect("Invalid stdout"),
+        "client,available,held,total,locked\n2,50.0000,0.0000,50.0000,false\n"
+    );
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn gzip() {
+    use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+    use std::io::Read;
+
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR"));
+    let input = dir.join("gzip.csv.gz");
+    let output = dir.join("gzip.out.csv.gz");
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    write!(encoder, "{HEADER}\n{}\n", ENTRIES.join("\n")).expect("Failed to compress");
+    fs::write(&input, encoder.finish().unwrap()).expect("Failed to write input");
+
+    let result = run(&[
+        "--quiet",
+        "--gzip",
+        "--input",
+        input.to_str().unwrap(),
+        "--output",
+        output.to_str().unwrap(),
+    ]);
+    assert!(result.status.success());
+
+    let compressed = fs::read(&output).expect("Failed to read output");
+    let mut csv = String::new();
+    GzDecoder::new(compressed.as_slice())
+        .read_to_string(&mut csv)
+        .expect("Failed to decompress");
+
+    let mut lines: Vec<&str> = csv.lines().collect();
+    lines[1..].sort();
+    assert_eq!(
+        lines,
+        [
+            "client,available,held,total,locked",
+            "1,100.0000,0.0000,100.0000,false",
+            "2,50.0000,0.0000,50.0000,false",
+        ]
+    );
+}
+
+#[test]
+fn delimiters() {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("delimiters.tsv");
+    let csv = format!("{}\n{}\n", HEADER, ENTRIES.join("\n")).replace(',', "\t");
+    fs::write(&path, csv).expect("Failed to write tsv");
+
+    let output = run(&[
+        "--quiet",
+        "--delimiter",
+        "\\t",
+        "--output-delimiter",
+        "|",
+        "--client",
+        "2",
+        path.to_str().unwrap(),
+    ]);
+
+    assert!(output.status.success());
+    assert_eq!(
+        std::str::from_utf8(&output.stdout).expect("Invalid stdout"),
+        "client|available|held|total|locked\n2|50.0000|0.0000|50.0000|false\n"
+    );
+}
+
+#[test]
+fn invalid_delimiter() {
+    let output = run(&["--delimiter", ";;", "input.csv"]);
+
+    assert!(!output.status.success());
+    assert!(stderr(&output).starts_with(
+        "error: invalid value ';;' for '--delimiter <CHAR>': Invalid delimiter \";;\"\n"
+    ));
+}
+
+#[test]
+fn no_header() {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("no_header.csv");
+    fs::write(&path, "deposit,1,1,100\ndispute,1,1,\ndeposit,2,2,50\n")
+        .expect("Failed to write csv");
+
+    let output = run(&["--quiet", "--no-header", "-c", "1", path.to_str().unwrap()]);
+
+    assert!(output.status.success());
+    assert_eq!(
+        std::str::from_utf8(&output.stdout).expect("Invalid stdout"),
+        "1,0.0000,100.0000,100.0000,false\n"
+    );
+}
+
+#[test]
+fn skip_lines() {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("skip_lines.csv");
+    let csv = format!(
+        "# Generated by Legacy System 1.0\n# Date: 2024-01-01\n{}\n{}\n",
+        HEADER,
+        ENTRIES.join("\n")
+    );
+    fs::write(&path, csv).expect("Failed to write csv");
+
+    let output = run(&[
+        "--quiet",
+        "--skip-lines",
+        "2",
+        "-c",
+        "2",
+        path.to_str().unwrap(),
+    ]);
+
+    assert!(output.status.success());
+    assert_eq!(
+        std::str::from_utf8(&output.stdout).expect("Invalid stdout"),
+        "client,available,held,total,locked\n2,50.0000,0.0000,50.0000,false\n"
+    );
+}
+
+#[test]
+fn max_deposits() {
+    let path = write_csv(
+        "max_deposits",
+        &["deposit,1,1,100", "deposit,1,2,100", "deposit,1,3,100"],
+    );
+    let output = run(&["--max-deposits", "2", path.to_str().unwrap()]);
+
+    assert!(output.status.success());
+    assert_eq!(
+        stderr(&output),
+        "row 3: deposit client 1: client with id 1 reached the limit of 2 deposits\n"
+    );
+    assert_eq!(
+        std::str::from_utf8(&output.stdout).expect("Invalid stdout"),
+        "client,available,held,total,locked\n1,200.0000,0.0000,200.0000,false\n"
+    );
+}
+
+#[test]
+fn invalid_max_deposits() {
+    let output = run(&["--max-deposits", "0", "input.csv"]);
+
+    assert!(!output.status.success());
+    assert!(stderr(&output).starts_with(
+        "error: invalid value '0' for '--max-deposits <N>': Invalid deposit limit 0\n"
+    ));
+}
+
+#[test]
+fn max_amount() {
+    let path = write_csv(
+        "max_amount",
+        &[
+            "deposit,1,1,9.9999",
+            "deposit,1,2,99999.9999",
+            "withdrawal,1,3,10",
+        ],
+    );
+    let output = run(&["--max-amount", "9.9999", path.to_str().unwrap()]);
+
+    assert!(output.status.success());
+    assert_eq!(
+        stderr(&output),
+        "row 2: deposit client 1: amount of 999999999 exceeds the maximum transaction amount of 99999\n\
+         row 3: withdrawal client 1: amount of 100000 exceeds the maximum transaction amount of 99999\n"
+    );
+    assert_eq!(
+        std::str::from_utf8(&output.stdout).expect("Invalid stdout"),
+        "client,available,held,total,locked\n1,9.9999,0.0000,9.9999,false\n"
+    );
+}
+
+#[test]
+fn ignore_frozen() {
+    let path = write_csv(
+        "ignore_frozen",
+        &[
+            "deposit,1,1,100",
+            "dispute,1,1,",
+            "chargeback,1,1,",
+            "deposit,1,2,50",
+            "withdrawal,2,3,10",
+        ],
+    );
+
+    let output = run(&[path.to_str().unwrap()]);
+    assert!(output.status.success());
+    assert_eq!(
+        stderr(&output),
+        "row 4: deposit client 1: account is frozen\n\
+         row 5: withdrawal client 2: client with id 2 does not exist\n"
+    );
+
+    let output = run(&["--ignore-frozen", path.to_str().unwrap()]);
+    assert!(output.status.success());
+    assert_eq!(
+        stderr(&output),
+        "row 5: withdrawal client 2: client with id 2 does not exist\n"
+    );
+    assert_eq!(
+        std::str::from_utf8(&output.stdout).expect("Invalid stdout"),
+        "client,available,held,total,locked\n1,0.0000,0.0000,0.0000,true\n"
+    );
+}
+
+#[test]
+fn ignore_frozen_transfer() {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("ignore_frozen_transfer.csv");
+    let csv = "type,client,tx,amount,target\n\
+               deposit,1,1,100,\n\
+               deposit,2,2,100,\n\
+               dispute,1,1,,\n\
+               chargeback,1,1,,\n\
+               transfer,1,3,1,2\n\
+               transfer,2,4,1,1\n";
+    fs::write(&path, csv).expect("Failed to write csv");
+
+    let output = run(&[path.to_str().unwrap()]);
+    assert!(output.status.success());
+    assert_eq!(
+        stderr(&output),
+        "row 5: transfer client 1: transfer from client 1 to client 2 failed: account is frozen\n\
+         row 6: transfer client 2: transfer from client 2 to client 1 failed: account is frozen\n"
+    );
+
+    let output = run(&["--ignore-frozen", path.to_str().unwrap()]);
+    assert!(output.status.success());
+    assert_eq!(stderr(&output), "");
+}
+
+#[test]
+fn precision() {
+    let path = write_csv("precision", &["deposit,1,1,1.23", "withdrawal,1,2,0.019"]);
+    let output = run(&["--precision", "2", path.to_str().unwrap()]);
+
+    assert!(output.status.success());
+    assert_eq!(
+        std::str::from_utf8(&output.stdout).expect("Invalid stdout"),
+        "client,available,held,total,locked\n1,1.22,0.00,1.22,false\n"
+    );
+}
+
+#[test]
+fn invalid_precision() {
+    let output = run(&["--precision", "3", "input.csv"]);
+
+    assert!(!output.status.success());
+    assert!(stderr(&output).starts_with(
+        "error: invalid value '3' for '--precision <N>': \
+         Invalid precision \"3\", expected 2, 4, 6, or 8\n"
+    ));
+}
+
+#[test]
+fn strict_sorted_ndjson() {
+    let path = write_csv("strict_sorted_ndjson", &["deposit,2,1,5", "deposit,1,2,10"]);
+    let output = run(&[
+        "--strict",
+        "--sort-output",
+        "--format",
+        "ndjson",
+        path.to_str().unwrap(),
+    ]);
+
+    assert!(output.status.success());
+    assert_eq!(
+        std::str::from_utf8(&output.stdout).expect("Invalid stdout"),
+        "{\"client\":1,\"available\":\"10.0000\",\"held\":\"0.0000\",\"total\":\"10.0000\",\"locked\":false}\n\
+         {\"client\":2,\"available\":\"5.0000\",\"held\":\"0.0000\",\"total\":\"5.0000\",\"locked\":false}\n"
+    );
+
+    let path = write_csv("strict", ENTRIES);
+    let output = run(&["--strict", path.to_str().unwrap()]);
+
+    assert!(!output.status.success());
+    assert_eq!(
+        stderr(&output),
+        "Fatal Error: attempt to debit amount of 1500000 exceeds avaiable funds of 1000000\n"
+    );
+}
+
+#[test]
+fn config_file() {
+    let path = write_csv("config_file", ENTRIES);
+    let config = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("config_file.toml");
+    fs::write(&config, "verbosity = \"quiet\"\nclient = [2]\n").expect("Failed to write config");
+
+    let output = run(&["--config", config.to_str().unwrap(), path.to_str().unwrap()]);
+
+    assert!(output.status.success());
+    assert_eq!(stderr(&output), "");
+    assert_eq!(
+        std::str::from_utf8(&output.stdout).expect("Invalid stdout"),
+        "client,available,held,total,locked\n2,50.0000,0.0000,50.0000,false\n"
+    );
+
+    // Command line flags take precedence over the config file
+    let output = run(&[
+        "--config",
+        config.to_str().unwrap(),
+        "--verbose",
+        "-c",
+        "1",
+        path.to_str().unwrap(),
+    ]);
+
+    assert!(output.status.success());
+    assert!(stderr(&output).ends_with("Processed 3 records, skipped 1\n"));
+    assert_eq!(
+        std::str::from_utf8(&output.stdout).expect("Invalid stdout"),
+        "client,available,held,total,locked\n1,100.0000,0.0000,100.0000,false\n"
+    );
+
+    // Settings switched on in the config file can be switched off
+    fs::write(&config, "strict = true\nno-header = true\n").expect("Failed to write config");
+    let output = run(&[
+        "--config",
+        config.to_str().unwrap(),
+        "--no-strict",
+        "--header",
+        "--quiet",
+        path.to_str().unwrap(),
+    ]);
+
+    assert!(output.status.success());
+    assert!(std::str::from_utf8(&output.stdout)
+        .expect("Invalid stdout")
+        .starts_with("client,available,held,total,locked\n"));
+}
+
+#[test]
+fn tracing() {
+    let path = write_csv("tracing", ENTRIES);
+    let output = Command::new(env!("CARGO_BIN_EXE_transactor"))
+        .args(["--quiet", path.to_str().unwrap()])
+        .env("RUST_LOG", "warn")
+        .output()
+        .expect("Failed to run transactor");
+
+    assert!(output.status.success());
+    let log = stderr(&output);
+    assert_eq!(log.lines().count(), 1);
+    assert!(log.contains("WARN"));
+    assert!(log.contains("operation refused row=2 operation=withdrawal client=1"));
+
+    // Refused operations recorded by the subscriber are not also reported as plain text
+    let output = Command::new(env!("CARGO_BIN_EXE_transactor"))
+        .arg(path.to_str().unwrap())
+        .env("RUST_LOG", "warn")
+        .output()
+        .expect("Failed to run transactor");
+
+    assert!(output.status.success());
+    let log = stderr(&output);
+    assert_eq!(log.lines().count(), 1);
+    assert!(log.contains("operation refused row=2 operation=withdrawal client=1"));
+}
+
+#[test]
+fn validate() {
+    let path = write_csv(
+        "validate",
+        &["deposit,1,1,100", "withdrawal,5,2,10", "deposit,one,3,100"],
+    );
+    let output = run(&["validate", path.to_str().unwrap()]);
+
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = std::str::from_utf8(&output.stdout).expect("Invalid stdout");
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0], "row 2: NoClient: client with id 5 does not exist");
+    assert!(lines[1].starts_with("row 3: ParseErrorAt: parse error at row 3"));
+
+    let path = write_csv("validate_ok", &ENTRIES[..1]);
+    let output = run(&["validate", path.to_str().unwrap()]);
+
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn stats() {
+    let path = write_csv("stats", ENTRIES);
+    let output = run(&["stats", "--quiet", path.to_str().unwrap()]);
+
+    assert!(output.status.success());
+    let stdout = std::str::from_utf8(&output.stdout).expect("Invalid stdout");
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(
+        lines[..6],
+        [
+            "key,value",
+            "accounts,2",
+            "frozen_accounts,0",
+            "available,150.0000",
+            "held,0.0000",
+            "total,150.0000"
+        ]
+    );
+    assert!(lines.contains(&"operations.deposit,2"));
+    assert!(lines.contains(&"operations.withdrawal,1"));
+    assert_eq!(lines.last(), Some(&"soft_errors,1"));
+
+    let output = run(&[
+        "stats",
+        "--quiet",
+        "--format",
+        "ndjson",
+        path.to_str().unwrap(),
+    ]);
+
+    assert!(output.status.success());
+    let stdout = std::str::from_utf8(&output.stdout).expect("Invalid stdout");
+    assert!(stdout.starts_with(r#"{"accounts":2,"frozen_accounts":0,"available":"150.0000""#));
+    assert!(stdout.ends_with("\"soft_errors\":1}\n"));
+}
@@ -0,0 +1,39 @@
+#![cfg(feature = "concurrent")]
+
+use std::{sync::Arc, thread};
+use transactor::concurrent::ConcurrentManager;
+
+const THREADS: u32 = 8;
+const DEPOSITS: u32 = 1_000;
+
+#[test]
+fn deposit_from_threads() {
+    let manager = Arc::new(ConcurrentManager::new());
+
+    // Every thread deposits into every client, with transaction IDs unique across threads
+    let handles: Vec<_> = (0..THREADS)
+        .map(|thread| {
+            let manager = Arc::clone(&manager);
+            thread::spawn(move || {
+                for i in 0..DEPOSITS {
+                    let client = (i % 4) as u16;
+                    let tx = thread * DEPOSITS + i;
+                    manager
+                        .deposit(client, tx, 100, None)
+                        .expect("Failed to deposit");
+                }
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        handle.join().expect("Thread panicked");
+    }
+
+    assert_eq!(manager.account_count(), 4);
+    for client in 0..4 {
+        let acct = manager.account(client).expect("No Account");
+        assert_eq!(acct.available(), u64::from(THREADS * DEPOSITS / 4) * 100);
+        assert_eq!(acct.deposit_count(), (THREADS * DEPOSITS / 4) as usize);
+    }
+}
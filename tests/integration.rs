@@ -0,0 +1,111 @@
+use std::io::Cursor;
+use transactor::{config::Config, manager::Manager, parse};
+
+/// Load the CSV into a new `Manager` and write the resulting accounts as CSV.
+fn process(csv: &str) -> String {
+    let config = Config::default();
+    let mut manager = Manager::new();
+    parse::load(Cursor::new(csv), &mut manager, &config).expect("Failed to load");
+
+    let mut output = Vec::new();
+    parse::dump_ref(&manager, &config, &mut output).expect("Failed to dump");
+    String::from_utf8(output).expect("Invalid output")
+}
+
+#[test]
+fn deposits_withdrawals() {
+    let csv = "type,client,tx,amount\n\
+               deposit,1,1,1.5\n\
+               deposit,1,2,2.0\n\
+               withdrawal,1,3,1.25\n\
+               withdrawal,1,4,10.0\n";
+
+    assert_eq!(
+        process(csv),
+        "client,available,held,total,locked\n\
+         1,2.2500,0.0000,2.2500,false\n"
+    );
+}
+
+#[test]
+fn dispute_resolve() {
+    let csv = "type,client,tx,amount\n\
+               deposit,1,1,5.0\n\
+               deposit,1,2,3.0\n\
+               dispute,1,1,\n";
+
+    assert_eq!(
+        process(csv),
+        "client,available,held,total,locked\n\
+         1,3.0000,5.0000,8.0000,false\n"
+    );
+    assert_eq!(
+        process(&format!("{csv}resolve,1,1,\n")),
+        "client,available,held,total,locked\n\
+         1,8.0000,0.0000,8.0000,false\n"
+    );
+}
+
+#[test]
+fn chargeback() {
+    let csv = "type,client,tx,amount\n\
+               deposit,1,1,5.0\n\
+               deposit,1,2,3.0\n\
+               dispute,1,1,\n\
+               chargeback,1,1,\n\
+               deposit,1,3,100.0\n";
+
+    assert_eq!(
+        process(csv),
+        "client,available,held,total,locked\n\
+         1,3.0000,0.0000,3.0000,true\n"
+    );
+}
+
+#[test]
+fn multiple_clients() {
+    let csv = "type,client,tx,amount\n\
+               deposit,2,1,1.0\n\
+               deposit,1,2,2.0\n\
+               deposit,3,3,3.0\n\
+               withdrawal,2,4,0.5\n\
+               dispute,3,3,\n\
+               dispute,1,1,\n\
+               deposit,1,5,0.0001\n";
+
+    assert_eq!(
+        process(csv),
+        "client,available,held,total,locked\n\
+         2,0.5000,0.0000,0.5000,false\n\
+         1,2.0001,0.0000,2.0001,false\n\
+         3,0.0000,3.0000,3.0000,false\n"
+    );
+}
+
+#[test]
+fn ndjson_matches_csv() {
+    let csv = "type,client,tx,amount\n\
+               deposit,1,1,1.5\n\
+               deposit,2,2,2.0\n\
+               withdrawal,1,3,0.25\n\
+               dispute,2,2,\n\
+               deposit,1,4,0.0001\n";
+    let ndjson = r#"{"type":"deposit","client":1,"tx":1,"amount":"1.5"}
+{"type":"deposit","client":2,"tx":2,"amount":"2.0"}
+{"type":"withdrawal","client":1,"tx":3,"amount":"0.25"}
+{"type":"dispute","client":2,"tx":2,"amount":null}
+
+{"type":"deposit","client":1,"tx":4,"amount":"0.0001"}
+"#;
+
+    let config = Config::default();
+    let mut manager = Manager::new();
+    parse::load_ndjson(Cursor::new(ndjson), &mut manager, &config).expect("Failed to load");
+
+    let mut output = Vec::new();
+    parse::dump_ref(&manager, &config, &mut output).expect("Failed to dump");
+    assert_eq!(
+        String::from_utf8(output).expect("Invalid output"),
+        process(csv)
+    );
+}
@@ -0,0 +1,74 @@
+#![cfg(feature = "async")]
+
+use tokio::io::AsyncWriteExt;
+use transactor::{config::Config, manager::Manager, parse};
+
+#[tokio::test]
+async fn load_data_async() {
+    let (mut client, server) = tokio::io::duplex(16);
+
+    let writer = tokio::spawn(async move {
+        client
+            .write_all(
+                b"type,client,tx,amount\ndeposit,1,1,100\nwithdrawal,1,2,150\ndeposit,2,3,50\n",
+            )
+            .await
+            .expect("Failed to write");
+    });
+
+    let mut manager = Manager::new();
+    let result = parse::load_data_async(server, &mut manager, &Config::default())
+        .await
+        .expect("Failed to load");
+    writer.await.expect("Writer panicked");
+
+    assert_eq!(result.processed, 3);
+    assert_eq!(result.skipped, 1);
+
+    let accounts = manager.drain_into_sorted_vec();
+    assert_eq!(accounts.len(), 2);
+    assert_eq!(accounts[0].1.available(), 1_000_000);
+    assert_eq!(accounts[1].1.available(), 500_000);
+}
+
+#[tokio::test]
+async fn load_data_async_chunks() {
+    let (mut client, server) = tokio::io::duplex(64);
+
+    // Enough rows to span several chunks, with a quoted description spanning the lines
+    // at the end of the first chunk, and a refused withdrawal late in the input
+    let mut csv = String::from("type,client,tx,amount,description\n");
+    for tx in 1..=3000 {
+        if tx == 1024 {
+            csv.push_str("deposit,2,1024,2,\"INV-1042\nline two\"\n");
+        } else {
+            csv.push_str(&format!("deposit,1,{tx},1,\n"));
+        }
+    }
+    csv.push_str("withdrawal,1,3001,5000,\n");
+
+    let writer = tokio::spawn(async move {
+        client
+            .write_all(csv.as_bytes())
+            .await
+            .expect("Failed to write");
+    });
+
+    let mut manager = Manager::new();
+    let result = parse::load_data_async(server, &mut manager, &Config::default())
+        .await
+        .expect("Failed to load");
+    writer.await.expect("Writer panicked");
+
+    assert_eq!(result.processed, 3001);
+    assert_eq!(result.skipped, 1);
+    assert_eq!(result.soft_errors[0].0, 3001);
+
+    let accounts = manager.drain_into_sorted_vec();
+    assert_eq!(accounts[0].1.available(), 29_990_000);
+    assert_eq!(accounts[1].1.available(), 20_000);
+    assert_eq!(
+        accounts[1].1.deposit_description(1024),
+        Some("INV-1042\nline two")
+    );
+}